@@ -0,0 +1,88 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+//! Measures the throughput of enqueue and dequeue pairs with increasing numbers of threads.
+//!
+//! Compare cache-padded (the default) and unpadded layouts by running both:-
+//!
+//! * `cargo bench --bench padding`
+//! * `cargo bench --bench padding --no-default-features`
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::CcSynchStrategy;
+use ::cc_queue::DsmSynchStrategy;
+use ::cc_queue::SynchStrategy;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::sync::Arc;
+use ::std::sync::Barrier;
+use ::std::thread;
+use ::std::thread::available_parallelism;
+use ::std::time::Duration;
+use ::std::time::Instant;
+
+
+const OPERATIONS_PER_THREAD: usize = 1_000_000;
+
+const RUNS: usize = 5;
+
+fn main()
+{
+	println!("cache padding: {}", if cfg!(feature = "cache-padding") { "enabled" } else { "disabled" });
+	
+	let parallelism = available_parallelism().map(|parallelism| parallelism.get()).unwrap_or(1);
+	let mut threads = vec![1, 2, 4, 8];
+	threads.retain(|&threads| threads <= parallelism.max(2));
+	
+	for &threads in threads.iter()
+	{
+		report("CC-Synch", threads, CcSynchStrategy);
+		report("DSM-Synch", threads, DsmSynchStrategy);
+	}
+}
+
+fn report<S: 'static + SynchStrategy + Copy + Send>(name: &str, threads: usize, strategy: S)
+{
+	// The best of several runs is the least disturbed by everything else running.
+	let best = (0 .. RUNS).map(|_| run(threads, strategy)).min().unwrap();
+	
+	let operations = (threads * OPERATIONS_PER_THREAD * 2) as f64;
+	println!("{:>9} {:>2} threads: {:>8.2} million operations per second", name, threads, operations / best.as_secs_f64() / 1_000_000.0);
+}
+
+// Each thread enqueues then dequeues, so the queue stays short and enqueuers and dequeuers contend.
+fn run<S: 'static + SynchStrategy + Send>(threads: usize, strategy: S) -> Duration
+{
+	let queue = Arc::new(CcQueue::with_synch_strategy(HeapAllocator, CcQueueConfiguration::default(), strategy));
+	let barrier = Arc::new(Barrier::new(threads + 1));
+	
+	let workers: Vec<_> = (0 .. threads).map(|_|
+	{
+		let queue = queue.clone();
+		let barrier = barrier.clone();
+		thread::spawn(move ||
+		{
+			let mut handle = queue.new_per_thread_handle();
+			barrier.wait();
+			
+			for item in 0 .. OPERATIONS_PER_THREAD
+			{
+				handle.enqueue(item).unwrap();
+				handle.dequeue().unwrap();
+			}
+		})
+	}).collect();
+	
+	barrier.wait();
+	let started = Instant::now();
+	for worker in workers
+	{
+		worker.join().unwrap();
+	}
+	started.elapsed()
+}
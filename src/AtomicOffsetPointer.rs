@@ -0,0 +1,64 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// An atomic `OffsetPointer`; likewise, one that is not null must never be moved.
+#[repr(transparent)]
+struct AtomicOffsetPointer<T>
+{
+	#[cfg(feature = "offset-pointers")] offset: AtomicIsize,
+	#[cfg(not(feature = "offset-pointers"))] pointer: AtomicPtr<T>,
+	marker: PhantomData<*mut T>,
+}
+
+impl<T> Debug for AtomicOffsetPointer<T>
+{
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result
+	{
+		f.debug_tuple("AtomicOffsetPointer").field(&self.load(Relaxed)).finish()
+	}
+}
+
+impl<T> AtomicOffsetPointer<T>
+{
+	#[inline(always)]
+	fn null() -> Self
+	{
+		Self
+		{
+			#[cfg(feature = "offset-pointers")] offset: AtomicIsize::new(0),
+			#[cfg(not(feature = "offset-pointers"))] pointer: AtomicPtr::new(null_mut()),
+			marker: PhantomData,
+		}
+	}
+	
+	// Result can be null
+	#[inline(always)]
+	fn load(&self, ordering: Ordering) -> *mut T
+	{
+		#[cfg(feature = "offset-pointers")]
+		{
+			OffsetPointer::from_offset(self as *const Self as usize, self.offset.load(ordering))
+		}
+		
+		#[cfg(not(feature = "offset-pointers"))]
+		{
+			self.pointer.load(ordering)
+		}
+	}
+	
+	#[inline(always)]
+	fn store(&self, pointer: *mut T, ordering: Ordering)
+	{
+		#[cfg(feature = "offset-pointers")]
+		{
+			self.offset.store(OffsetPointer::to_offset(self as *const Self as usize, pointer), ordering)
+		}
+		
+		#[cfg(not(feature = "offset-pointers"))]
+		{
+			self.pointer.store(pointer, ordering)
+		}
+	}
+}
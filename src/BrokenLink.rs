@@ -0,0 +1,28 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// A link in the chain of a queue's nodes that can not be followed (see `QueueInspection`).
+/// Each holds the number of items found before the link; the link is either the queue's `head` or follows the node of the last of those items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BrokenLink
+{
+	/// The link is null, where a node was expected, or to memory that can not be a node allocated from the file.
+	Dangling(usize),
+	
+	/// The link is to a node already found, so the chain never ends.
+	Cycle(usize),
+}
+
+impl Display for BrokenLink
+{
+	#[inline(always)]
+	fn fmt(&self, formatter: &mut Formatter) -> fmt::Result
+	{
+		match *self
+		{
+			BrokenLink::Dangling(items) => write!(formatter, "dangling link after {} items", items),
+			BrokenLink::Cycle(items) => write!(formatter, "cycle after {} items", items),
+		}
+	}
+}
@@ -0,0 +1,43 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// The size of a cache line on this architecture.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))] const CACHE_LINE_SIZE: usize = 64;
+#[cfg(any(target_arch = "aarch64", target_arch = "powerpc64"))] const CACHE_LINE_SIZE: usize = 128;
+#[cfg(target_arch = "s390x")] const CACHE_LINE_SIZE: usize = 256;
+#[cfg(any(target_arch = "arm", target_arch = "hexagon", target_arch = "mips", target_arch = "riscv32", target_arch = "sparc"))] const CACHE_LINE_SIZE: usize = 32;
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64", target_arch = "s390x", target_arch = "arm", target_arch = "hexagon", target_arch = "mips", target_arch = "riscv32", target_arch = "sparc")))] const CACHE_LINE_SIZE: usize = 64;
+
+const _: () = assert!(!cfg!(feature = "cache-padding") || align_of::<CacheAligned<u8>>() == CACHE_LINE_SIZE);
+
+// Aligned to, and so padded to a multiple of, `CACHE_LINE_SIZE`, so that nothing else shares its cache line.
+// Does nothing without the `cache-padding` feature.
+#[derive(Debug, Default)]
+#[repr(C)]
+#[cfg_attr(all(feature = "cache-padding", any(target_arch = "x86", target_arch = "x86_64")), repr(align(64)))]
+#[cfg_attr(all(feature = "cache-padding", any(target_arch = "aarch64", target_arch = "powerpc64")), repr(align(128)))]
+#[cfg_attr(all(feature = "cache-padding", target_arch = "s390x"), repr(align(256)))]
+#[cfg_attr(all(feature = "cache-padding", any(target_arch = "arm", target_arch = "hexagon", target_arch = "mips", target_arch = "riscv32", target_arch = "sparc")), repr(align(32)))]
+#[cfg_attr(all(feature = "cache-padding", not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64", target_arch = "powerpc64", target_arch = "s390x", target_arch = "arm", target_arch = "hexagon", target_arch = "mips", target_arch = "riscv32", target_arch = "sparc"))), repr(align(64)))]
+struct CacheAligned<T>(T);
+
+impl<T> Deref for CacheAligned<T>
+{
+	type Target = T;
+	
+	#[inline(always)]
+	fn deref(&self) -> &Self::Target
+	{
+		&self.0
+	}
+}
+
+impl<T> DerefMut for CacheAligned<T>
+{
+	#[inline(always)]
+	fn deref_mut(&mut self) -> &mut Self::Target
+	{
+		&mut self.0
+	}
+}
@@ -4,38 +4,69 @@
 
 /// This is the cc queue object.
 /// It is safe to send references between threads.
-/// Each thread accessing the queue should call `new_per_thread_handle`.
-/// The queue supports being dropped and all Nodes being freed, however...
-/// It does not have any way to free the data owned by the nodes, so a memory leak is quite likely.
-/// Instead, it is better to call `clear()` with a callback which can free node data, which requires that there are no `PerQueueThreadHandle` in existence, even for the current thread.
+/// Threads can enqueue and dequeue directly, using a per-thread handle that is created on first use and freed when the thread exits (or the queue is dropped).
+/// Alternatively, a thread can call `new_per_thread_handle` and manage its own handle.
+/// Data is moved into the queue by value and stored inline in the queue's nodes.
+/// The queue is unbounded unless created with a capacity (see `bounded()`), in which case enqueues wait for space.
+/// The queue can be closed (see `close()`), after which enqueues fail and, once drained, dequeues report that the queue is closed; this makes it suitable for use as a multi-producer, multi-consumer channel.
+/// The queue supports being dropped and all Nodes being freed.
+/// Any data still owned by the nodes is passed to the queue's destructor, which by default drops it (see `with_destructor()`).
+/// Alternatively, call `clear()` with a callback which takes ownership of the remaining data, which requires that there are no `PerQueueThreadHandle` in existence, even for the current thread.
 /// Rust's borrow checker should be able to enforce this.
+/// Processes can use a queue at the same time if it is allocated from memory shared between them (see `CcQueueConfiguration.shared_between_processes` and `attach()`).
 #[derive(Debug)]
-pub struct CcQueue<T, A: Allocator>(NonNull<QueueInternal<T, A>>);
+pub struct CcQueue<T, A: Allocator, S: SynchStrategy = CcSynchStrategy>
+{
+	queue_internal: NonNull<QueueInternal<T, A, S>>,
+	per_queue_thread_handle_internals: Arc<PerQueueThreadHandleInternals<T, A>>,
+	clusters: Clusters,
+	consumers: Waiters,
+	producers: Waiters,
+	
+	// Per-thread handles are allocated using this process's allocator, rather than the one in the queue's memory, which belongs to the process that created the queue if it is shared between processes.
+	allocator: A,
+	
+	// An attached queue belongs to the process that created it, so is not dropped.
+	attached: bool,
+}
 
-unsafe impl<T, A: Allocator> Send for CcQueue<T, A>
+unsafe impl<T: Send, A: Allocator, S: SynchStrategy> Send for CcQueue<T, A, S>
 {
 }
 
-unsafe impl<T, A: Allocator> Sync for CcQueue<T, A>
+unsafe impl<T: Send, A: Allocator, S: SynchStrategy> Sync for CcQueue<T, A, S>
 {
 }
 
-impl<T, A: Allocator> AllocatorOpened<A> for CcQueue<T, A>
+impl<T, A: Allocator, S: SynchStrategy> AllocatorOpened<A> for CcQueue<T, A, S>
 {
 	#[inline(always)]
 	fn allocator_opened(&mut self, allocator: A)
 	{
-		unsafe { self.0.as_mut() }.allocator_opened(allocator)
+		unsafe { self.queue_internal.as_mut() }.allocator_opened(allocator)
 	}
 }
 
-impl<T, A: Allocator> Drop for CcQueue<T, A>
+impl<T, A: Allocator, S: SynchStrategy> Drop for CcQueue<T, A, S>
 {
 	#[inline(always)]
 	fn drop(&mut self)
 	{
-		let queue_internal = self.0;
-		let allocator = unsafe { self.0.as_ref() }.allocator().clone();
+		// A forked child's copy of a queue shared between processes, and of its per-thread handles, belongs to the parent.
+		if self.per_queue_thread_handle_internals.is_forked()
+		{
+			return
+		}
+		
+		self.per_queue_thread_handle_internals.free_all();
+		
+		if self.attached
+		{
+			return
+		}
+		
+		let queue_internal = self.queue_internal;
+		let allocator = self.queue_internal().allocator().clone();
 		unsafe { drop_in_place(queue_internal.as_ptr()) };
 		QueueInternal::free_after_drop(queue_internal, allocator);
 	}
@@ -46,25 +77,589 @@ impl<T, A: Allocator> CcQueue<T, A>
 	/// Create a new queue.
 	/// Specify an allocator implementation which provides memory for the queue and its nodes.
 	/// This can be the heap, or it can be a persistent memory or mmap'd file.
+	/// Any items remaining when the queue is dropped are dropped.
 	#[inline(always)]
 	pub fn new(allocator: A) -> Self
 	{
-		CcQueue(QueueInternal::new(allocator))
+		Self::configured(allocator, CcQueueConfiguration::default())
+	}
+	
+	/// Create a new queue.
+	/// Specify an allocator implementation which provides memory for the queue and its nodes.
+	/// This can be the heap, or it can be a persistent memory or mmap'd file.
+	/// Any items remaining when the queue is dropped are passed to `destructor`; use `forget` to leak them.
+	#[inline(always)]
+	pub fn with_destructor(allocator: A, destructor: fn(T)) -> Self
+	{
+		Self::configured(allocator, CcQueueConfiguration { destructor, ..CcQueueConfiguration::default() })
+	}
+	
+	/// Create a new bounded queue, which holds at most `capacity` items.
+	/// Specify an allocator implementation which provides memory for the queue and its nodes.
+	/// This can be the heap, or it can be a persistent memory or mmap'd file.
+	/// Any items remaining when the queue is dropped are dropped.
+	///
+	/// Panics if `capacity` is zero.
+	#[inline(always)]
+	pub fn bounded(allocator: A, capacity: usize) -> Self
+	{
+		Self::configured(allocator, CcQueueConfiguration { capacity: Some(capacity), ..CcQueueConfiguration::default() })
+	}
+	
+	/// Create a new queue.
+	/// Specify an allocator implementation which provides memory for the queue and its nodes.
+	/// This can be the heap, or it can be a persistent memory or mmap'd file.
+	///
+	/// Panics if `configuration.capacity` is zero, if `configuration.clustering` is explicit with zero clusters, if `configuration.help_bound` is not valid, or if `configuration` is shared between processes and either durable or with operation records.
+	#[inline(always)]
+	pub fn configured(allocator: A, configuration: CcQueueConfiguration<T>) -> Self
+	{
+		Self::with_synch_strategy(allocator, configuration, CcSynchStrategy)
+	}
+}
+
+impl<T, A: Allocator, S: SynchStrategy> CcQueue<T, A, S>
+{
+	/// Create a new queue which combines concurrent requests using the strategy `S`, such as `DsmSynchStrategy`, rather than the default of `CcSynchStrategy`.
+	/// Specify an allocator implementation which provides memory for the queue and its nodes.
+	/// This can be the heap, or it can be a persistent memory or mmap'd file.
+	///
+	/// Panics if `configuration.capacity` is zero, if `configuration.clustering` is explicit with zero clusters, if `configuration.help_bound` is not valid, or if `configuration` is shared between processes and either durable or with operation records.
+	#[inline(always)]
+	pub fn with_synch_strategy(allocator: A, configuration: CcQueueConfiguration<T>, _strategy: S) -> Self
+	{
+		let capacity = match configuration.capacity
+		{
+			None => usize::MAX,
+			Some(capacity) =>
+			{
+				assert_ne!(capacity, 0, "capacity can not be zero");
+				capacity
+			}
+		};
+		
+		configuration.help_bound.assert_valid();
+		
+		assert!(!(configuration.durable && configuration.shared_between_processes), "a queue can not be both durable and shared between processes");
+		assert!(configuration.operation_records == 0 || !configuration.shared_between_processes, "a queue shared between processes can not have operation records");
+		
+		let clusters = Clusters::new(configuration.clustering);
+		
+		let queue_internal = QueueInternal::new(allocator.clone(), configuration.destructor, capacity, clusters.count(), configuration.help_bound, configuration.durable, configuration.operation_records, configuration.shared_between_processes);
+		Self::process_side(queue_internal, allocator, clusters, false)
+	}
+	
+	/// Re-creates a queue from the memory returned by `into_raw()`, calling `allocator_opened()` with `allocator`, which must refer to the memory the queue was allocated from, eg an `MmapFileAllocator` reopened after the process that created the queue exited.
+	/// The queue keeps the data in it, its capacity, its help bound, whether it is durable, its operation records and whether it is closed.
+	/// `configuration` supplies the destructor and the clustering, which must result in the same number of clusters as when the queue was created.
+	///
+	/// Fails if the header at the start of the queue's memory shows that it is not a queue, or was created with a different layout, eg for a different `T` or `S`, or by an incompatible build of this crate.
+	///
+	/// Panics if the number of clusters differs.
+	///
+	/// # Safety
+	/// `raw` must have been returned by `into_raw()`, and the queue not already re-created; the header catches a different `T` of another size or alignment, but not of the same ones.
+	/// The data in the queue must still be valid; if the process has exited, `T` must not contain pointers to memory other than the allocator's.
+	#[inline(always)]
+	pub unsafe fn from_raw(allocator: A, raw: NonNull<()>, configuration: CcQueueConfiguration<T>) -> Result<Self, QueueHeaderError>
+	{
+		Self::reopen(allocator, raw, configuration, false)
+	}
+	
+	/// As `from_raw()`, but for a queue whose process may have exited abnormally, eg been killed, whilst using it, rather than calling `into_raw()`; `raw` would have been recorded using `as_raw()`.
+	/// The queue keeps the data that was enqueued and not dequeued, apart from that of any enqueue or dequeue in progress, which either happened or did not.
+	/// If the enqueue or dequeue was by a recoverable per-thread handle, `recorded_operation()` reports which; otherwise, memory for the data of an enqueue in progress is leaked, as is memory for per-thread handles.
+	///
+	/// If the queue was not durable (see `CcQueueConfiguration.durable`), this only recovers from the process exiting, not from the operating system or machine crashing before the memory was written back.
+	///
+	/// Fails, and panics, as `from_raw()`.
+	///
+	/// # Safety
+	/// As `from_raw()`; in addition, the allocator must have been recovered, eg with `MmapFileAllocator::recover()`.
+	#[inline(always)]
+	pub unsafe fn recover(allocator: A, raw: NonNull<()>, configuration: CcQueueConfiguration<T>) -> Result<Self, QueueHeaderError>
+	{
+		Self::reopen(allocator, raw, configuration, true)
+	}
+	
+	/// The queue's memory, which can be recorded (eg with `MmapFileAllocator::set_root()`) so that the queue can be recovered with `recover()` should the process exit abnormally.
+	#[inline(always)]
+	pub fn as_raw(&self) -> NonNull<()>
+	{
+		self.queue_internal.cast()
+	}
+	
+	#[inline(always)]
+	unsafe fn reopen(allocator: A, raw: NonNull<()>, configuration: CcQueueConfiguration<T>, recover: bool) -> Result<Self, QueueHeaderError>
+	{
+		let mut queue_internal = Self::validated(raw)?;
+		
+		let clusters = Clusters::new(configuration.clustering);
+		assert_eq!(clusters.count(), queue_internal.as_ref().clusters(), "clustering must result in the same number of clusters as when the queue was created");
+		
+		queue_internal.as_mut().reopened(allocator.clone(), configuration.destructor);
+		if recover
+		{
+			queue_internal.as_mut().recover()
+		}
+		
+		Ok(Self::process_side(queue_internal, allocator, clusters, false))
+	}
+	
+	/// Attaches to a queue that another process created, and is still using, in memory shared with this process, so that both can use it at the same time; `raw` would have been recorded using `as_raw()`, eg with `MmapFileAllocator::set_root()`.
+	/// The queue must have been created with `CcQueueConfiguration.shared_between_processes`.
+	/// A forked child of a process using such a queue must attach to it too, rather than use its copy, which it can only drop.
+	/// `configuration` supplies the clustering, which must result in the same number of clusters as when the queue was created; its destructor is not used, as dropping an attached queue frees this process's per-thread handles but not the queue, which is dropped by the process that created it.
+	///
+	/// Fails as `from_raw()`.
+	///
+	/// Panics if the queue is not shared between processes, or if the number of clusters differs.
+	///
+	/// # Safety
+	/// `raw` must be a queue of the same `T` that has not been freed, allocated from memory that `allocator` maps at the same address as in the process that created it, eg using `MmapFileAllocator::open_shared_memory()`, or inherited by forking.
+	/// `T` must not contain pointers to memory other than the allocator's.
+	#[inline(always)]
+	pub unsafe fn attach(allocator: A, raw: NonNull<()>, configuration: CcQueueConfiguration<T>) -> Result<Self, QueueHeaderError>
+	{
+		let queue_internal = Self::validated(raw)?;
+		assert!(queue_internal.as_ref().shared_between_processes, "queue must be shared between processes to be attached");
+		
+		let clusters = Clusters::new(configuration.clustering);
+		assert_eq!(clusters.count(), queue_internal.as_ref().clusters(), "clustering must result in the same number of clusters as when the queue was created");
+		
+		Ok(Self::process_side(queue_internal, allocator, clusters, true))
+	}
+	
+	// Checks the header, which is at the start of the queue's memory, before anything else in it is read.
+	#[inline(always)]
+	unsafe fn validated(raw: NonNull<()>) -> Result<NonNull<QueueInternal<T, A, S>>, QueueHeaderError>
+	{
+		raw.cast::<QueueHeader>().as_ref().validate::<T, A, S>()?;
+		Ok(raw.cast())
+	}
+	
+	#[inline(always)]
+	fn process_side(queue_internal: NonNull<QueueInternal<T, A, S>>, allocator: A, clusters: Clusters, attached: bool) -> Self
+	{
+		let shared_between_processes = unsafe { queue_internal.as_ref() }.shared_between_processes;
+		
+		CcQueue
+		{
+			queue_internal,
+			per_queue_thread_handle_internals: Arc::new(PerQueueThreadHandleInternals::new(shared_between_processes)),
+			clusters,
+			consumers: Waiters::new(shared_between_processes),
+			producers: Waiters::new(shared_between_processes),
+			allocator,
+			attached,
+		}
+	}
+	
+	/// Consumes the queue without freeing it or the data in it, returning its memory, from which it can be re-created using `from_raw()`.
+	/// Intended for an allocator whose memory persists, such as `MmapFileAllocator`, so that the queue survives the process exiting (see `MmapFileAllocator::set_root()`); with other allocators, the queue is leaked.
+	/// Per-thread handles created implicitly are freed.
+	/// An attached queue (see `attach()`) is only detached, as it is still in use by the process that created it.
+	///
+	/// Panics if this process is a forked child of the one using the queue (see `attach()`).
+	#[inline(always)]
+	pub fn into_raw(self) -> NonNull<()>
+	{
+		self.assert_not_forked();
+		
+		let this = ManuallyDrop::new(self);
+		this.per_queue_thread_handle_internals.free_all();
+		
+		let mut queue_internal = this.queue_internal;
+		unsafe
+		{
+			if !this.attached
+			{
+				queue_internal.as_mut().close_for_reopening();
+			}
+			
+			drop(read(&this.per_queue_thread_handle_internals));
+			drop(read(&this.clusters));
+			drop(read(&this.consumers));
+			drop(read(&this.producers));
+			drop(read(&this.allocator));
+		}
+		queue_internal.cast()
 	}
 	
 	/// Create a new per-thread handle.
+	///
+	/// Panics if this process is a forked child of the one using the queue (see `attach()`).
+	#[inline(always)]
+	pub fn new_per_thread_handle<'queue>(&'queue self) -> PerQueueThreadHandle<'queue, T, A, S>
+	{
+		self.assert_not_forked();
+		
+		PerQueueThreadHandle(self, PerQueueThreadHandleInternal::new(self.allocator.clone(), self.clusters.cluster_of_current_thread(), None))
+	}
+	
+	/// Create a new per-thread handle whose single enqueues and dequeues are recorded in operation record `record` (see `CcQueueConfiguration.operation_records`), so that, after the queue is recovered from a crash (see `recover()`), `recorded_operation()` reports whether the last of them took effect, and what it returned.
+	/// Batches and closing the queue are not recorded.
+	/// A thread should use the same record whenever it creates a handle, so that it knows which record to check.
+	///
+	/// Panics if `record` is not less than the number of operation records, if another per-thread handle is using it, or if this process is a forked child of the one using the queue (see `attach()`).
+	#[inline(always)]
+	pub fn new_recoverable_per_thread_handle<'queue>(&'queue self, record: usize) -> PerQueueThreadHandle<'queue, T, A, S>
+	{
+		self.assert_not_forked();
+		
+		let record = self.queue_internal().record(record);
+		unsafe { record.as_ref() }.acquire();
+		PerQueueThreadHandle(self, PerQueueThreadHandleInternal::new(self.allocator.clone(), self.clusters.cluster_of_current_thread(), Some(record)))
+	}
+	
+	/// The last single enqueue or dequeue recorded in operation record `record` by a per-thread handle created with `new_recoverable_per_thread_handle()`, including before the queue was recovered.
+	/// Records keep a copy of the data enqueued or dequeued, hence `T` must be `Copy`.
+	///
+	/// Panics if `record` is not less than the number of operation records.
+	#[inline(always)]
+	pub fn recorded_operation(&mut self, record: usize) -> RecordedOperation<T> where T: Copy
+	{
+		unsafe { self.queue_internal().record(record).as_ref() }.recorded()
+	}
+	
+	/// The number of operation records (see `CcQueueConfiguration.operation_records`).
+	#[inline(always)]
+	pub fn operation_records(&self) -> usize
+	{
+		self.queue_internal().record_count
+	}
+	
+	/// The number of clusters used for hierarchical combining (H-Synch); one unless configured otherwise (see `CcQueueConfiguration.clustering`).
+	#[inline(always)]
+	pub fn clusters(&self) -> usize
+	{
+		self.clusters.count()
+	}
+	
+	/// The maximum number of enqueue requests a combining thread currently applies before handing over; only changes if the help bound is adaptive (see `CcQueueConfiguration.help_bound`).
+	#[inline(always)]
+	pub fn enqueue_help_bound(&self) -> usize
+	{
+		self.queue_internal().enq.help_bound()
+	}
+	
+	/// The maximum number of dequeue requests a combining thread currently applies before handing over; only changes if the help bound is adaptive (see `CcQueueConfiguration.help_bound`).
+	#[inline(always)]
+	pub fn dequeue_help_bound(&self) -> usize
+	{
+		self.queue_internal().deq.help_bound()
+	}
+	
+	/// Has the queue been closed?
+	/// Once closed, a queue stays closed.
 	#[inline(always)]
-	pub fn new_per_thread_handle<'queue>(&'queue self) -> PerQueueThreadHandle<'queue, T, A>
+	pub fn is_closed(&self) -> bool
 	{
-		PerQueueThreadHandle(self, PerQueueThreadHandleInternal::new(unsafe { self.0.as_ref() }.allocator().clone()))
+		self.queue_internal().is_closed()
 	}
 	
 	/// Clear the queue.
 	/// Only works on a queue that is acquiescent.
+	/// `free_data` is passed ownership of each item remaining in the queue, in queue order.
+	/// The queue is empty afterwards and can continue to be used.
+	#[inline(always)]
+	pub fn clear<FreeData: FnMut(T)>(&mut self, mut free_data: FreeData)
+	{
+		let mut queue_internal = self.queue_internal;
+		unsafe { queue_internal.as_mut() }.clear(&mut self.allocator, &mut free_data)
+	}
+	
+	#[inline(always)]
+	fn queue_internal(&self) -> &QueueInternal<T, A, S>
+	{
+		unsafe { self.queue_internal.as_ref() }
+	}
+	
+	#[inline(always)]
+	fn assert_not_forked(&self)
+	{
+		assert!(!self.per_queue_thread_handle_internals.is_forked(), "a forked child must attach to a queue shared between processes, using CcQueue::attach(), rather than use its copy");
+	}
+	
+	#[inline(always)]
+	fn enqueue_using(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, data: T) -> Result<(), T>
+	{
+		if !self.queue_internal().is_bounded()
+		{
+			return self.try_enqueue_using(handle, data).map_err(EnqueueError::into_inner)
+		}
+		
+		let mut data = Some(data);
+		self.producers.wait_until(|| match self.try_enqueue_using(handle, data.take().unwrap())
+		{
+			Ok(()) => Some(Ok(())),
+			Err(EnqueueError::Full(full)) =>
+			{
+				data = Some(full);
+				None
+			}
+			Err(EnqueueError::Closed(closed)) => Some(Err(closed)),
+		})
+	}
+	
+	#[inline(always)]
+	fn try_enqueue_using(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, data: T) -> Result<(), EnqueueError<T>>
+	{
+		if self.queue_internal().enqueue(handle, data)?
+		{
+			self.consumers.notify_one()
+		}
+		Ok(())
+	}
+	
+	#[inline(always)]
+	fn enqueue_batch_using<I: IntoIterator<Item = T>>(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, batch: I) -> Result<(), Vec<T>>
+	{
+		let queue = self.queue_internal();
+		
+		let mut chain = NodeChain::new(handle, batch);
+		if chain.is_none()
+		{
+			return Ok(())
+		}
+		
+		self.producers.wait_until(||
+		{
+			let (enqueued, was_empty, result) = queue.enqueue_chain(handle, chain.take().unwrap(), None);
+			if was_empty
+			{
+				self.consumers.notify_many(enqueued)
+			}
+			
+			match result
+			{
+				Ok(()) => Some(Ok(())),
+				Err(EnqueueError::Full(remainder)) =>
+				{
+					chain = Some(remainder);
+					None
+				}
+				Err(EnqueueError::Closed(remainder)) =>
+				{
+					let mut data = Vec::with_capacity(remainder.length);
+					remainder.into_data(handle, &mut data);
+					Some(Err(data))
+				}
+			}
+		})
+	}
+	
+	#[inline(always)]
+	fn dequeue_using(&self, handle: &mut PerQueueThreadHandleInternal<T, A>) -> Result<T, DequeueError>
+	{
+		let queue = self.queue_internal();
+		
+		let data = queue.dequeue(handle);
+		if data.is_ok()
+		{
+			self.dequeued(queue);
+			if queue.is_bounded()
+			{
+				self.producers.notify_one()
+			}
+		}
+		data
+	}
+	
+	#[inline(always)]
+	fn dequeue_batch_using(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, maximum: usize, into: &mut Vec<T>) -> Result<usize, DequeueError>
+	{
+		if maximum == 0
+		{
+			return Ok(0)
+		}
+		
+		let queue = self.queue_internal();
+		
+		let dequeued = queue.dequeue_batch(handle, maximum, into);
+		if let Ok(dequeued) = dequeued
+		{
+			self.dequeued(queue);
+			if queue.is_bounded()
+			{
+				self.producers.notify_many(dequeued)
+			}
+		}
+		dequeued
+	}
+	
+	// Enqueues only notify consumers if the queue was empty, so a consumer that leaves data behind notifies another, lest it wait whilst there is data.
+	#[inline(always)]
+	fn dequeued(&self, queue: &QueueInternal<T, A, S>)
+	{
+		if !queue.is_empty()
+		{
+			self.consumers.notify_one()
+		}
+	}
+	
+	#[inline(always)]
+	fn dequeue_wait_using(&self, handle: &mut PerQueueThreadHandleInternal<T, A>) -> Option<T>
+	{
+		self.consumers.wait_until(|| Self::unless_empty(self.dequeue_using(handle))).ok()
+	}
+	
+	#[inline(always)]
+	fn dequeue_timeout_using(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, timeout: Duration) -> Result<T, DequeueError>
+	{
+		// A timeout too large to represent is treated as waiting forever.
+		let deadline = Instant::now().checked_add(timeout);
+		self.consumers.wait_until_deadline(|| Self::unless_empty(self.dequeue_using(handle)), deadline).unwrap_or(Err(DequeueError::TimedOut))
+	}
+	
+	#[inline(always)]
+	fn dequeue_deadline_using(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, deadline: Instant) -> Result<T, DequeueError>
+	{
+		self.consumers.wait_until_deadline(|| Self::unless_empty(self.dequeue_using(handle)), Some(deadline)).unwrap_or(Err(DequeueError::TimedOut))
+	}
+	
+	#[inline(always)]
+	fn close_using(&self, handle: &mut PerQueueThreadHandleInternal<T, A>)
+	{
+		self.queue_internal().close(handle);
+		self.consumers.notify_all();
+		self.producers.notify_all()
+	}
+	
+	// Waiting for data only ends if there is data or the queue is closed.
+	#[inline(always)]
+	fn unless_empty(result: Result<T, DequeueError>) -> Option<Result<T, DequeueError>>
+	{
+		match result
+		{
+			Err(DequeueError::Empty) => None,
+			result => Some(result),
+		}
+	}
+}
+
+impl<T: 'static, A: 'static + Allocator, S: 'static + SynchStrategy> CcQueue<T, A, S>
+{
+	/// Enqueue data using this thread's implicit per-thread handle.
+	/// The data is moved into the queue and is owned by it until dequeued.
+	/// If the queue is bounded, parks the thread until there is space.
+	/// If the queue is closed, the data is returned.
+	#[inline(always)]
+	pub fn enqueue(&self, data: T) -> Result<(), T>
+	{
+		self.with_thread_local_handle(|handle| self.enqueue_using(handle, data))
+	}
+	
+	/// Enqueue data using this thread's implicit per-thread handle, unless the queue is full or closed, in which case the data is returned.
+	/// The data is moved into the queue and is owned by it until dequeued.
+	/// An unbounded queue is never full.
+	#[inline(always)]
+	pub fn try_enqueue(&self, data: T) -> Result<(), EnqueueError<T>>
+	{
+		self.with_thread_local_handle(|handle| self.try_enqueue_using(handle, data))
+	}
+	
+	/// Enqueue a batch of data, in order, using this thread's implicit per-thread handle.
+	/// The batch is enqueued using one combining request, rather than one per item.
+	/// If the queue is bounded, as much of the batch as there is space for is enqueued, parking the thread until there is space for the rest.
+	/// If the queue is closed, the data not yet enqueued is returned.
+	#[inline(always)]
+	pub fn enqueue_batch<I: IntoIterator<Item = T>>(&self, batch: I) -> Result<(), Vec<T>>
+	{
+		// Collected before the implicit per-thread handle is borrowed, as the batch's iterator may itself use the queue from this thread.
+		let batch: Vec<T> = batch.into_iter().collect();
+		self.with_thread_local_handle(|handle| self.enqueue_batch_using(handle, batch))
+	}
+	
+	/// Dequeue data using this thread's implicit per-thread handle.
+	/// Ownership of the data is moved out of the queue to the caller.
+	/// Returns `DequeueError::Closed` rather than `DequeueError::Empty` once the queue is closed and has been drained.
+	#[inline(always)]
+	pub fn dequeue(&self) -> Result<T, DequeueError>
+	{
+		self.with_thread_local_handle(|handle| self.dequeue_using(handle))
+	}
+	
+	/// Dequeue up to `maximum` items of data, in order, using this thread's implicit per-thread handle, appending them to `into`.
+	/// The batch is dequeued using one combining request, rather than one per item.
+	/// Returns the number of items dequeued, which is only zero if `maximum` is zero.
+	/// Returns `DequeueError::Closed` rather than `DequeueError::Empty` once the queue is closed and has been drained.
+	#[inline(always)]
+	pub fn dequeue_batch(&self, maximum: usize, into: &mut Vec<T>) -> Result<usize, DequeueError>
+	{
+		self.with_thread_local_handle(|handle| self.dequeue_batch_using(handle, maximum, into))
+	}
+	
+	/// Dequeue data using this thread's implicit per-thread handle, parking the thread until data is available if the queue is empty.
+	/// Ownership of the data is moved out of the queue to the caller.
+	/// Returns `None` once the queue is closed and has been drained.
+	#[inline(always)]
+	pub fn dequeue_wait(&self) -> Option<T>
+	{
+		self.with_thread_local_handle(|handle| self.dequeue_wait_using(handle))
+	}
+	
+	/// Dequeue data using this thread's implicit per-thread handle, parking the thread until data is available if the queue is empty.
+	/// Returns `DequeueError::TimedOut` if no data became available before `timeout` elapsed, or `DequeueError::Closed` once the queue is closed and has been drained.
+	/// Ownership of the data is moved out of the queue to the caller.
+	#[inline(always)]
+	pub fn dequeue_timeout(&self, timeout: Duration) -> Result<T, DequeueError>
+	{
+		self.with_thread_local_handle(|handle| self.dequeue_timeout_using(handle, timeout))
+	}
+	
+	/// Dequeue data using this thread's implicit per-thread handle, parking the thread until data is available if the queue is empty.
+	/// Returns `DequeueError::TimedOut` if no data became available before `deadline`, or `DequeueError::Closed` once the queue is closed and has been drained.
+	/// Ownership of the data is moved out of the queue to the caller.
+	#[inline(always)]
+	pub fn dequeue_deadline(&self, deadline: Instant) -> Result<T, DequeueError>
+	{
+		self.with_thread_local_handle(|handle| self.dequeue_deadline_using(handle, deadline))
+	}
+	
+	/// Returns a future that dequeues data using the implicit per-thread handle of whichever thread polls it.
+	/// Whilst the queue is empty, the future registers its task's waker, which is woken by a subsequent enqueue or by closing the queue.
+	/// Resolves to `None` once the queue is closed and has been drained.
+	/// Does not depend on any particular asynchronous runtime.
+	#[inline(always)]
+	pub fn dequeue_async(&self) -> DequeueFuture<'_, T, A, S> where T: Send
+	{
+		DequeueFuture::new(self)
+	}
+	
+	/// Returns a stream of dequeued data; each item is dequeued as if by `dequeue_async()`.
+	/// The stream ends once the queue is closed and has been drained.
+	#[inline(always)]
+	pub fn dequeue_stream(&self) -> DequeueStream<'_, T, A, S> where T: Send
+	{
+		DequeueStream(DequeueFuture::new(self))
+	}
+	
+	/// Close the queue using this thread's implicit per-thread handle.
+	/// Subsequent enqueues fail, returning their data; data already enqueued can still be dequeued, after which dequeues report `DequeueError::Closed`.
+	/// Wakes all parked threads and registered wakers.
+	/// Closing an already closed queue does nothing.
+	#[inline(always)]
+	pub fn close(&self)
+	{
+		self.with_thread_local_handle(|handle| self.close_using(handle))
+	}
+	
 	#[inline(always)]
-	pub fn clear<FreeData: Fn(NonNull<T>)>(&mut self, free_data: FreeData)
+	fn with_thread_local_handle<R, User: FnOnce(&mut PerQueueThreadHandleInternal<T, A>) -> R>(&self, user: User) -> R
 	{
-		let mut queue_internal = self.0;
-		unsafe { queue_internal.as_mut() }.clear(&free_data)
+		self.assert_not_forked();
+		
+		match ThreadLocalPerQueueThreadHandles::find_or_create(&self.per_queue_thread_handle_internals, &self.allocator, &self.clusters)
+		{
+			Some(mut handle) => user(unsafe { handle.as_mut() }),
+			
+			// Thread-local storage has been destroyed; this thread is exiting.
+			None =>
+			{
+				let mut handle = self.new_per_thread_handle();
+				user(handle.handle())
+			}
+		}
 	}
 }
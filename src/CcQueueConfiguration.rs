@@ -0,0 +1,75 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// Configuration of a `CcQueue`, set when it is created.
+#[derive(Debug)]
+pub struct CcQueueConfiguration<T>
+{
+	/// Run on each item still in the queue when it is dropped.
+	/// Defaults to `drop`; use `forget` to leak them.
+	pub destructor: fn(T),
+	
+	/// Maximum number of items the queue can hold; must not be zero.
+	/// Defaults to `None`, which is unbounded.
+	pub capacity: Option<usize>,
+	
+	/// How threads are grouped into clusters for hierarchical combining (H-Synch).
+	/// Defaults to `Clustering::Single`.
+	pub clustering: Clustering,
+	
+	/// The maximum number of requests a combining thread applies before handing over to another thread; either fixed or adaptive.
+	/// Defaults to `HelpBound::Fixed(HelpBound::DEFAULT)`.
+	pub help_bound: HelpBound,
+	
+	/// Whether the combiners write back the queue's links to persistent storage, in an order that a crash can not leave torn, using the allocator's `persist()`; recover the queue after a crash with `CcQueue::recover()`.
+	/// Only useful with an allocator whose memory persists, such as `MmapFileAllocator`, and slower.
+	/// Defaults to `false`.
+	pub durable: bool,
+	
+	/// The number of operation records, in which recoverable per-thread handles record their last single enqueue or dequeue so that, after a crash, it can be determined whether it took effect (see `CcQueue::new_recoverable_per_thread_handle()`).
+	/// Defaults to zero.
+	pub operation_records: usize,
+	
+	/// Whether other processes will use the queue at the same time as the one creating it (see `CcQueue::attach()`); the allocator must then allocate from memory shared between them, such as `MmapFileAllocator::create_shared_memory()`.
+	/// As other processes do not notify them, threads parked waiting on the queue then also poll it, every millisecond; asynchronous dequeues are only woken by this process.
+	/// A queue can not be both shared between processes and durable, nor have operation records, as another process's enqueue may be in progress whenever they are read.
+	/// Defaults to `false`.
+	pub shared_between_processes: bool,
+}
+
+impl<T> Default for CcQueueConfiguration<T>
+{
+	#[inline(always)]
+	fn default() -> Self
+	{
+		Self
+		{
+			destructor: drop,
+			capacity: None,
+			clustering: Clustering::default(),
+			help_bound: HelpBound::default(),
+			durable: false,
+			operation_records: 0,
+			shared_between_processes: false,
+		}
+	}
+}
+
+impl<T> Clone for CcQueueConfiguration<T>
+{
+	#[inline(always)]
+	fn clone(&self) -> Self
+	{
+		Self
+		{
+			destructor: self.destructor,
+			capacity: self.capacity,
+			clustering: self.clustering.clone(),
+			help_bound: self.help_bound,
+			durable: self.durable,
+			operation_records: self.operation_records,
+			shared_between_processes: self.shared_between_processes,
+		}
+	}
+}
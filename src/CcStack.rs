@@ -0,0 +1,97 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// This is the cc stack object, the CC-Stack of Fatourou and Kallimanis.
+/// It is safe to send references between threads.
+/// Pushes and pops are combined, using CC-Synch, by a single combiner.
+/// Each thread needs a per-thread handle (see `new_per_thread_handle()`) to push and pop.
+/// Data is moved into the stack by value and stored inline in the stack's nodes.
+/// The stack supports being dropped and all Nodes being freed.
+/// Any data still owned by the nodes is passed to the stack's destructor, which by default drops it (see `with_destructor()`).
+/// Alternatively, call `clear()` with a callback which takes ownership of the remaining data, which requires that there are no `PerStackThreadHandle` in existence.
+/// Rust's borrow checker should be able to enforce this.
+#[derive(Debug)]
+pub struct CcStack<T, A: Allocator>
+{
+	stack_internal: NonNull<StackInternal<T, A>>,
+}
+
+unsafe impl<T: Send, A: Allocator> Send for CcStack<T, A>
+{
+}
+
+unsafe impl<T: Send, A: Allocator> Sync for CcStack<T, A>
+{
+}
+
+impl<T, A: Allocator> AllocatorOpened<A> for CcStack<T, A>
+{
+	#[inline(always)]
+	fn allocator_opened(&mut self, allocator: A)
+	{
+		unsafe { self.stack_internal.as_mut() }.allocator_opened(allocator)
+	}
+}
+
+impl<T, A: Allocator> Drop for CcStack<T, A>
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		let stack_internal = self.stack_internal;
+		let allocator = self.stack_internal().allocator().clone();
+		unsafe { drop_in_place(stack_internal.as_ptr()) };
+		StackInternal::free_after_drop(stack_internal, allocator);
+	}
+}
+
+impl<T, A: Allocator> CcStack<T, A>
+{
+	/// Create a new stack.
+	/// Specify an allocator implementation which provides memory for the stack and its nodes.
+	/// This can be the heap, or it can be a persistent memory or mmap'd file.
+	/// Any items remaining when the stack is dropped are dropped.
+	#[inline(always)]
+	pub fn new(allocator: A) -> Self
+	{
+		Self::with_destructor(allocator, drop)
+	}
+	
+	/// Create a new stack.
+	/// Specify an allocator implementation which provides memory for the stack and its nodes.
+	/// This can be the heap, or it can be a persistent memory or mmap'd file.
+	/// Any items remaining when the stack is dropped are passed to `destructor`; use `forget` to leak them.
+	#[inline(always)]
+	pub fn with_destructor(allocator: A, destructor: fn(T)) -> Self
+	{
+		CcStack
+		{
+			stack_internal: StackInternal::new(allocator, destructor),
+		}
+	}
+	
+	/// Create a new per-thread handle.
+	#[inline(always)]
+	pub fn new_per_thread_handle<'stack>(&'stack self) -> PerStackThreadHandle<'stack, T, A>
+	{
+		PerStackThreadHandle(self, PerStackThreadHandleInternal::new(self.stack_internal().allocator().clone()))
+	}
+	
+	/// Clear the stack.
+	/// Only works on a stack that is acquiescent.
+	/// `free_data` is passed ownership of each item remaining in the stack, most recently pushed first.
+	/// The stack is empty afterwards and can continue to be used.
+	#[inline(always)]
+	pub fn clear<FreeData: FnMut(T)>(&mut self, mut free_data: FreeData)
+	{
+		let mut stack_internal = self.stack_internal;
+		unsafe { stack_internal.as_mut() }.clear(&mut free_data)
+	}
+	
+	#[inline(always)]
+	fn stack_internal(&self) -> &StackInternal<T, A>
+	{
+		unsafe { self.stack_internal.as_ref() }
+	}
+}
@@ -0,0 +1,97 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// A CC-Synch combining object, which makes any sequential state safe to use concurrently.
+/// Threads submit operations on the state using a per-thread handle (see `new_per_thread_handle()`).
+/// Rather than each thread taking a lock, one thread at a time, the combiner, applies its own and other waiting threads' operations in turn, so the state stays in the combiner's cache.
+/// This is the same machinery that `CcQueue` uses for enqueues and dequeues.
+///
+/// Operations run on whichever thread is the combiner, so must be `Send`, as must their results.
+/// An operation must not panic; if it does, threads waiting for the combiner will wait forever.
+#[derive(Debug)]
+pub struct CcSynch<S>
+{
+	synch: UnsafeCell<Synch<S>>,
+	state: UnsafeCell<S>,
+}
+
+unsafe impl<S: Send> Send for CcSynch<S>
+{
+}
+
+unsafe impl<S: Send> Sync for CcSynch<S>
+{
+}
+
+impl<S> Drop for CcSynch<S>
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		self.synch.get_mut().free_nodes(&mut HeapAllocator)
+	}
+}
+
+impl<S: Default> Default for CcSynch<S>
+{
+	#[inline(always)]
+	fn default() -> Self
+	{
+		Self::new(S::default())
+	}
+}
+
+impl<S> CcSynch<S>
+{
+	/// Create a new combining object for `state`.
+	#[inline(always)]
+	pub fn new(state: S) -> Self
+	{
+		Self
+		{
+			synch: UnsafeCell::new(Synch::new(&mut HeapAllocator)),
+			state: UnsafeCell::new(state),
+		}
+	}
+	
+	/// Create a new per-thread handle.
+	#[inline(always)]
+	pub fn new_per_thread_handle(&self) -> CcSynchHandle<'_, S>
+	{
+		CcSynchHandle(self, SynchHandle::new(&mut HeapAllocator))
+	}
+	
+	/// Access the state directly; no operations can be in progress as there are no handles.
+	#[inline(always)]
+	pub fn get_mut(&mut self) -> &mut S
+	{
+		unsafe { &mut *self.state.get() }
+	}
+	
+	/// Takes back the state.
+	#[inline(always)]
+	pub fn into_inner(self) -> S
+	{
+		let mut this = ManuallyDrop::new(self);
+		this.synch.get_mut().free_nodes(&mut HeapAllocator);
+		unsafe { read(this.state.get()) }
+	}
+	
+	// Each request is a type-erased closure, as each may be of a different type.
+	#[inline(always)]
+	fn apply<R: Send, Apply: FnOnce(&mut S) -> R + Send>(&self, synch_handle: &mut SynchHandle<S>, apply: Apply) -> R
+	{
+		let mut apply = Some(apply);
+		let mut response = None;
+		
+		{
+			let mut request = |state: &mut S| response = Some((apply.take().unwrap())(state));
+			let mut request: &mut dyn FnMut(&mut S) = &mut request;
+			
+			unsafe { Synch::ccsynch_apply(&self.synch, synch_handle, |state: &UnsafeCell<S>, request: &mut &mut dyn FnMut(&mut S)| request(&mut *state.get()), &self.state, &mut request) }
+		}
+		
+		response.unwrap()
+	}
+}
@@ -0,0 +1,30 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// This structure is created for each thread that wants to apply operations using a `CcSynch`.
+#[derive(Debug)]
+pub struct CcSynchHandle<'synch, S: 'synch>(&'synch CcSynch<S>, SynchHandle<S>);
+
+impl<'synch, S> Drop for CcSynchHandle<'synch, S>
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		self.1.free_nodes(&mut HeapAllocator)
+	}
+}
+
+impl<'synch, S> CcSynchHandle<'synch, S>
+{
+	/// Apply an operation to the state, returning its result.
+	/// The operation is applied by whichever thread is the combiner, which may be this thread or another one.
+	/// Operations are applied one at a time, in the order in which they were submitted.
+	#[inline(always)]
+	pub fn apply<R: Send, Apply: FnOnce(&mut S) -> R + Send>(&mut self, apply: Apply) -> R
+	{
+		let synch = self.0;
+		
+		synch.apply(&mut self.1, apply)
+	}
+}
@@ -0,0 +1,26 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// CC-Synch, in which a thread waiting for its request to be applied spins on a node shared with the thread that made the previous request.
+/// Best on cache-coherent systems; the default.
+#[derive(Debug, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct CcSynchStrategy;
+
+#[allow(private_interfaces)]
+impl SynchStrategy for CcSynchStrategy
+{
+	const IDENTIFIER: u64 = 1;
+	
+	#[inline(always)]
+	fn new_synch<T, A: Allocator>(allocator: &mut A) -> Synch<T>
+	{
+		Synch::new(allocator)
+	}
+	
+	#[inline(always)]
+	unsafe fn synch_apply<T, A: Allocator, State, D, Apply: Fn(&State, &mut D)>(synch: &UnsafeCell<Synch<T>>, synch_handle: &mut SynchHandle<T>, _allocator: &mut A, apply: Apply, state: &State, data: &mut D, combiner_lock: Option<&CombinerLock>, help_bound: &CombiningHelpBound)
+	{
+		Synch::ccsynch_apply_with_lock(synch, synch_handle, apply, state, data, combiner_lock, help_bound)
+	}
+}
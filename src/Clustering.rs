@@ -0,0 +1,50 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// How threads are grouped into clusters for hierarchical combining (H-Synch).
+/// Threads first combine their requests with other threads in the same cluster; only one combiner per cluster at a time then competes for the queue itself.
+/// A thread's cluster is decided when its per-thread handle is created.
+#[derive(Debug, Clone)]
+pub enum Clustering
+{
+	/// All threads are in one cluster; this is plain CC-Synch.
+	Single,
+	
+	/// One cluster per NUMA node with at least one CPU, read from a directory laid out like `/sys/devices/system/node` (see `Clustering::numa_nodes()`).
+	/// A thread is in the cluster of the CPU it is running on when its handle is created.
+	/// If the directory can not be read, or there is only one NUMA node, this is the same as `Single`.
+	NumaNodes(PathBuf),
+	
+	/// An explicit mapping of threads to clusters.
+	Explicit
+	{
+		/// The number of clusters; must not be zero.
+		clusters: usize,
+		
+		/// Called on a thread when its handle is created; the result is reduced modulo `clusters`.
+		cluster_of_current_thread: fn() -> usize,
+	},
+}
+
+impl Default for Clustering
+{
+	#[inline(always)]
+	fn default() -> Self
+	{
+		Clustering::Single
+	}
+}
+
+impl Clustering
+{
+	/// The location of NUMA nodes on Linux.
+	pub const SYSFS_NUMA_NODES: &'static str = "/sys/devices/system/node";
+	
+	/// One cluster per NUMA node, as listed in `/sys/devices/system/node`.
+	#[inline(always)]
+	pub fn numa_nodes() -> Self
+	{
+		Clustering::NumaNodes(PathBuf::from(Self::SYSFS_NUMA_NODES))
+	}
+}
@@ -0,0 +1,161 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// A resolved `Clustering`.
+#[derive(Debug)]
+enum Clusters
+{
+	Single,
+	
+	NumaNodes
+	{
+		// Indexed by CPU number; CPUs not in any node are missing or `usize::MAX`.
+		cluster_of_cpu: Vec<usize>,
+		clusters: usize,
+	},
+	
+	Explicit
+	{
+		clusters: usize,
+		cluster_of_current_thread: fn() -> usize,
+	},
+}
+
+impl Clusters
+{
+	// Panics if an explicit number of clusters is zero.
+	#[inline(always)]
+	fn new(clustering: Clustering) -> Self
+	{
+		match clustering
+		{
+			Clustering::Single => Clusters::Single,
+			
+			Clustering::NumaNodes(path) =>
+			{
+				let cpus_of_nodes = Self::cpus_of_numa_nodes(&path);
+				if cpus_of_nodes.len() <= 1
+				{
+					return Clusters::Single
+				}
+				
+				let mut cluster_of_cpu = Vec::new();
+				for (cluster, cpus) in cpus_of_nodes.iter().enumerate()
+				{
+					for &cpu in cpus
+					{
+						if cpu >= cluster_of_cpu.len()
+						{
+							cluster_of_cpu.resize(cpu + 1, usize::MAX)
+						}
+						cluster_of_cpu[cpu] = cluster
+					}
+				}
+				
+				Clusters::NumaNodes
+				{
+					cluster_of_cpu,
+					clusters: cpus_of_nodes.len(),
+				}
+			}
+			
+			Clustering::Explicit { clusters, cluster_of_current_thread } =>
+			{
+				assert_ne!(clusters, 0, "clusters can not be zero");
+				Clusters::Explicit
+				{
+					clusters,
+					cluster_of_current_thread,
+				}
+			}
+		}
+	}
+	
+	#[inline(always)]
+	fn count(&self) -> usize
+	{
+		match *self
+		{
+			Clusters::Single => 1,
+			Clusters::NumaNodes { clusters, .. } => clusters,
+			Clusters::Explicit { clusters, .. } => clusters,
+		}
+	}
+	
+	// Always less than `count()`.
+	#[inline(always)]
+	fn cluster_of_current_thread(&self) -> usize
+	{
+		match *self
+		{
+			Clusters::Single => 0,
+			
+			Clusters::NumaNodes { ref cluster_of_cpu, .. } => match Self::current_cpu().and_then(|cpu| cluster_of_cpu.get(cpu))
+			{
+				Some(&cluster) if cluster != usize::MAX => cluster,
+				_ => 0,
+			},
+			
+			Clusters::Explicit { clusters, cluster_of_current_thread } => cluster_of_current_thread() % clusters,
+		}
+	}
+	
+	// Nodes are ordered by node number; nodes without CPUs (eg memory-only nodes) are omitted.
+	fn cpus_of_numa_nodes(path: &Path) -> Vec<Vec<usize>>
+	{
+		let entries = match read_dir(path)
+		{
+			Err(_) => return Vec::new(),
+			Ok(entries) => entries,
+		};
+		
+		let mut nodes: Vec<(usize, Vec<usize>)> = entries.filter_map(|entry|
+		{
+			let entry = entry.ok()?;
+			let node = entry.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+			let cpus = Self::parse_cpu_list(&read_to_string(entry.path().join("cpulist")).ok()?)?;
+			if cpus.is_empty()
+			{
+				None
+			}
+			else
+			{
+				Some((node, cpus))
+			}
+		}).collect();
+		
+		nodes.sort_by_key(|&(node, _)| node);
+		nodes.into_iter().map(|(_, cpus)| cpus).collect()
+	}
+	
+	// Parses a Linux CPU list, eg `0-3,8,10-11`.
+	fn parse_cpu_list(cpu_list: &str) -> Option<Vec<usize>>
+	{
+		let mut cpus = Vec::new();
+		
+		for range in cpu_list.trim().split(',').filter(|range| !range.is_empty())
+		{
+			let mut bounds = range.splitn(2, '-');
+			let first: usize = bounds.next()?.parse().ok()?;
+			let last: usize = match bounds.next()
+			{
+				None => first,
+				Some(last) => last.parse().ok()?,
+			};
+			cpus.extend(first ..= last)
+		}
+		
+		Some(cpus)
+	}
+	
+	// The `processor` field of `/proc/thread-self/stat`.
+	fn current_cpu() -> Option<usize>
+	{
+		let stat = read_to_string("/proc/thread-self/stat").ok()?;
+		
+		// The second field, the command, is in parentheses and may contain spaces; the processor is the 39th field.
+		let after_command = &stat[stat.rfind(')')? + 1 ..];
+		after_command.split_whitespace().nth(39 - 3)?.parse().ok()
+	}
+}
@@ -0,0 +1,34 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// A test-and-test-and-set spin lock, held by a cluster's combiner whilst it applies requests.
+#[derive(Debug)]
+struct CombinerLock(AtomicBool);
+
+impl CombinerLock
+{
+	#[inline(always)]
+	fn new() -> Self
+	{
+		CombinerLock(AtomicBool::new(false))
+	}
+	
+	#[inline(always)]
+	fn lock(&self)
+	{
+		while self.0.compare_exchange_weak(false, true, Acquire, Relaxed).is_err()
+		{
+			while self.0.load(Relaxed)
+			{
+				PAUSE();
+			}
+		}
+	}
+	
+	#[inline(always)]
+	fn unlock(&self)
+	{
+		self.0.store(false, Release)
+	}
+}
@@ -0,0 +1,75 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// The help bound used by combiners, which, if adaptive, is tuned at the end of each combining pass.
+#[derive(Debug)]
+struct CombiningHelpBound
+{
+	help_bound: HelpBound,
+	
+	// Only changes if adaptive.
+	// Combiners in different H-Synch clusters may tune it at the same time; the last to store wins.
+	current: AtomicUsize,
+}
+
+impl CombiningHelpBound
+{
+	// `help_bound` must be valid.
+	#[inline(always)]
+	fn new(help_bound: HelpBound) -> Self
+	{
+		Self
+		{
+			current: AtomicUsize::new(help_bound.initial()),
+			help_bound,
+		}
+	}
+	
+	#[inline(always)]
+	fn current(&self) -> usize
+	{
+		self.current.load(Relaxed)
+	}
+	
+	// Returns the bound for this combining pass, and, if adaptive, when it started.
+	#[inline(always)]
+	fn combining_started(&self) -> (usize, Option<Instant>)
+	{
+		let started = match self.help_bound
+		{
+			HelpBound::Fixed(_) => None,
+			HelpBound::Adaptive { .. } => Some(Instant::now()),
+		};
+		(self.current(), started)
+	}
+	
+	// `applied` is the number of requests applied in the combining pass, including the combiner's own.
+	#[inline(always)]
+	fn combining_finished(&self, bound: usize, started: Option<Instant>, applied: usize)
+	{
+		if let HelpBound::Adaptive { minimum, maximum, target_hold_time } = self.help_bound
+		{
+			let held = started.map(|started| started.elapsed()).unwrap_or_default();
+			
+			// Grows by an eighth, but halves, so that the bound backs off faster than it grows.
+			let tuned = if held > target_hold_time
+			{
+				(bound / 2).max(minimum)
+			}
+			else if applied >= bound
+			{
+				bound.saturating_add(bound / 8).saturating_add(1).min(maximum)
+			}
+			else
+			{
+				return
+			};
+			
+			if tuned != bound
+			{
+				self.current.store(tuned, Relaxed)
+			}
+		}
+	}
+}
@@ -0,0 +1,35 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// Why no data was dequeued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DequeueError
+{
+	/// The queue is empty, but more data may be enqueued.
+	Empty,
+	
+	/// The queue is empty and has been closed, so no more data will ever be enqueued.
+	Closed,
+	
+	/// A timeout or deadline passed whilst the queue was empty, but more data may be enqueued.
+	TimedOut,
+}
+
+impl Display for DequeueError
+{
+	#[inline(always)]
+	fn fmt(&self, formatter: &mut Formatter) -> fmt::Result
+	{
+		match *self
+		{
+			DequeueError::Empty => write!(formatter, "queue is empty"),
+			DequeueError::Closed => write!(formatter, "queue is empty and closed"),
+			DequeueError::TimedOut => write!(formatter, "queue is empty and timed out"),
+		}
+	}
+}
+
+impl Error for DequeueError
+{
+}
@@ -0,0 +1,49 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// A future that dequeues data, waiting asynchronously whilst the queue is empty.
+/// Created by `CcQueue.dequeue_async()`.
+/// Uses the implicit per-thread handle of whichever thread polls it, so it can move between threads.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct DequeueFuture<'queue, T: 'static + Send, A: 'static + Allocator, S: 'static + SynchStrategy = CcSynchStrategy>
+{
+	queue: &'queue CcQueue<T, A, S>,
+	key: Option<usize>,
+}
+
+impl<'queue, T: 'static + Send, A: 'static + Allocator, S: 'static + SynchStrategy> Drop for DequeueFuture<'queue, T, A, S>
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		self.queue.consumers.cancel(&mut self.key)
+	}
+}
+
+impl<'queue, T: 'static + Send, A: 'static + Allocator, S: 'static + SynchStrategy> Future for DequeueFuture<'queue, T, A, S>
+{
+	type Output = Option<T>;
+	
+	#[inline(always)]
+	fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output>
+	{
+		let this = self.get_mut();
+		let queue = this.queue;
+		queue.consumers.poll_until(&mut this.key, context, || CcQueue::<T, A, S>::unless_empty(queue.dequeue()).map(Result::ok))
+	}
+}
+
+impl<'queue, T: 'static + Send, A: 'static + Allocator, S: 'static + SynchStrategy> DequeueFuture<'queue, T, A, S>
+{
+	#[inline(always)]
+	fn new(queue: &'queue CcQueue<T, A, S>) -> Self
+	{
+		Self
+		{
+			queue,
+			key: None,
+		}
+	}
+}
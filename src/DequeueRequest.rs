@@ -0,0 +1,16 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+#[derive(Debug)]
+struct DequeueRequest<T>
+{
+	// Never zero.
+	maximum: usize,
+	
+	// Set by the combiner.
+	result: Result<NodeChain<T>, DequeueError>,
+	
+	// The operation record of a recoverable per-thread handle's single dequeue; updated by the combiner.
+	record: Option<NonNull<OperationRecord<T>>>,
+}
@@ -0,0 +1,36 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// A stream of dequeued data, waiting asynchronously whilst the queue is empty.
+/// Created by `CcQueue.dequeue_stream()`.
+/// Uses the implicit per-thread handle of whichever thread polls it, so it can move between threads.
+/// Implements `futures_core::Stream` if the `futures` feature is enabled; otherwise, use `poll_next()` directly.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct DequeueStream<'queue, T: 'static + Send, A: 'static + Allocator, S: 'static + SynchStrategy = CcSynchStrategy>(DequeueFuture<'queue, T, A, S>);
+
+#[cfg(feature = "futures")]
+impl<'queue, T: 'static + Send, A: 'static + Allocator, S: 'static + SynchStrategy> ::futures_core::Stream for DequeueStream<'queue, T, A, S>
+{
+	type Item = T;
+	
+	#[inline(always)]
+	fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>>
+	{
+		DequeueStream::poll_next(self, context)
+	}
+}
+
+impl<'queue, T: 'static + Send, A: 'static + Allocator, S: 'static + SynchStrategy> DequeueStream<'queue, T, A, S>
+{
+	/// Attempts to dequeue the next data, registering the current task to be woken if the queue is empty.
+	/// Returns `Poll::Ready(None)` once the queue is closed and has been drained.
+	/// Has the same signature as `futures_core::Stream::poll_next()`.
+	#[inline(always)]
+	pub fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<T>>
+	{
+		let future = &mut self.get_mut().0;
+		Pin::new(future).poll(context)
+	}
+}
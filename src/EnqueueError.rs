@@ -0,0 +1,60 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// Why data could not be enqueued.
+/// The data is returned to the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnqueueError<T>
+{
+	/// The queue is bounded and is full.
+	Full(T),
+	
+	/// The queue has been closed and will never accept data again.
+	Closed(T),
+}
+
+impl<T> Display for EnqueueError<T>
+{
+	#[inline(always)]
+	fn fmt(&self, formatter: &mut Formatter) -> fmt::Result
+	{
+		match *self
+		{
+			EnqueueError::Full(_) => write!(formatter, "queue is full"),
+			EnqueueError::Closed(_) => write!(formatter, "queue is closed"),
+		}
+	}
+}
+
+impl<T: Debug> Error for EnqueueError<T>
+{
+}
+
+impl<T> EnqueueError<T>
+{
+	/// Takes back ownership of the data that could not be enqueued.
+	#[inline(always)]
+	pub fn into_inner(self) -> T
+	{
+		match self
+		{
+			EnqueueError::Full(data) => data,
+			EnqueueError::Closed(data) => data,
+		}
+	}
+	
+	/// Was the queue full?
+	#[inline(always)]
+	pub fn is_full(&self) -> bool
+	{
+		matches!(*self, EnqueueError::Full(_))
+	}
+	
+	/// Was the queue closed?
+	#[inline(always)]
+	pub fn is_closed(&self) -> bool
+	{
+		matches!(*self, EnqueueError::Closed(_))
+	}
+}
@@ -0,0 +1,23 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+#[derive(Debug)]
+struct EnqueueRequest<T>
+{
+	// `None` requests that the queue is closed.
+	// Set by the combiner to whatever was not enqueued.
+	chain: Option<NodeChain<T>>,
+	
+	// Set by the combiner to the number of nodes enqueued.
+	enqueued: usize,
+	
+	// Set by the combiner if everything enqueued before the nodes had been dequeued, so that consumers may be waiting for them.
+	was_empty: bool,
+	
+	// Set by the combiner.
+	outcome: EnqueueOutcome,
+	
+	// The operation record of a recoverable per-thread handle's single enqueue; updated by the combiner.
+	record: Option<NonNull<OperationRecord<T>>>,
+}
@@ -0,0 +1,35 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// Counts the times that this process, or its ancestors since counting started, forked, so that a queue shared between processes can tell whether it is being used by a forked child of the process that created or attached it, to which its per-thread handles do not belong.
+// Counting starts when `start()` is first called.
+static FORKS: AtomicUsize = AtomicUsize::new(0);
+
+static FORKS_COUNTED: Once = Once::new();
+
+struct Forks;
+
+impl Forks
+{
+	// Returns the current count.
+	#[inline(always)]
+	fn start() -> usize
+	{
+		#[cfg(unix)] FORKS_COUNTED.call_once(|| assert_eq!(unsafe { pthread_atfork(None, None, Some(Self::forked)) }, 0, "pthread_atfork() failed"));
+		Self::count()
+	}
+	
+	#[inline(always)]
+	fn count() -> usize
+	{
+		FORKS.load(Relaxed)
+	}
+	
+	// Called in the child after a fork.
+	#[cfg(unix)]
+	extern "C" fn forked()
+	{
+		FORKS.fetch_add(1, Relaxed);
+	}
+}
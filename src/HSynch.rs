@@ -0,0 +1,104 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// H-Synch: one CC-Synch (or DSM-Synch) list per cluster, the combiners of which take turns using a lock.
+// With one cluster, this is plain CC-Synch (or DSM-Synch) and the lock is never used.
+#[derive(Debug)]
+struct HSynch<T, S: SynchStrategy>
+{
+	// `cluster_count` of them, allocated from the queue's allocator.
+	clusters: OffsetPointer<UnsafeCell<Synch<T>>>,
+	
+	// Never zero.
+	cluster_count: usize,
+	
+	combiner_lock: CombinerLock,
+	
+	help_bound: CombiningHelpBound,
+	
+	strategy: PhantomData<S>,
+}
+
+impl<T, S: SynchStrategy> HSynch<T, S>
+{
+	// Initializes `this` in place, as `clusters` must not be moved.
+	#[inline(always)]
+	unsafe fn hsynch_new<A: Allocator>(this: *mut Self, clusters: usize, help_bound: HelpBound, allocator: &mut A)
+	{
+		write(&mut (*this).clusters, OffsetPointer::null());
+		(*this).clusters.set(allocator.align_malloc_cache_line_size_array(clusters).as_ptr());
+		write(&mut (*this).cluster_count, clusters);
+		write(&mut (*this).combiner_lock, CombinerLock::new());
+		write(&mut (*this).help_bound, CombiningHelpBound::new(help_bound));
+		write(&mut (*this).strategy, PhantomData);
+		
+		(*this).synch_init(allocator)
+	}
+	
+	// Used when memory containing an `HSynch` is re-opened, eg after being persisted; the previous contents, apart from the clusters themselves, their number and the configured help bound, are not valid.
+	// An adaptive help bound starts being tuned afresh.
+	#[inline(always)]
+	unsafe fn hsynch_init<A: Allocator>(this: *mut Self, allocator: &mut A)
+	{
+		let help_bound = (*this).help_bound.help_bound;
+		write(&mut (*this).combiner_lock, CombinerLock::new());
+		write(&mut (*this).help_bound, CombiningHelpBound::new(help_bound));
+		(*this).synch_init(allocator)
+	}
+	
+	// Must be called, with the allocator the clusters came from, before dropping; the `HSynch` is not used again.
+	#[inline(always)]
+	fn free<A: Allocator>(&mut self, allocator: &mut A)
+	{
+		self.free_nodes(allocator);
+		allocator.free_cache_line_size_array(unsafe { self.clusters.get_non_null() }, self.cluster_count)
+	}
+	
+	// Frees the clusters' nodes, but not the clusters themselves; `hsynch_init()` must be called before the `HSynch` is used again.
+	#[inline(always)]
+	fn free_nodes<A: Allocator>(&mut self, allocator: &mut A)
+	{
+		for cluster in 0 .. self.cluster_count
+		{
+			unsafe { &mut * self.cluster(cluster).get() }.free_nodes(allocator)
+		}
+	}
+	
+	#[inline(always)]
+	fn help_bound(&self) -> usize
+	{
+		self.help_bound.current()
+	}
+	
+	// `cluster` must be less than the number of clusters, and always the same for a particular `synch_handle`.
+	#[inline(always)]
+	unsafe fn hsynch_apply<A: Allocator, State, D, Apply: Fn(&State, &mut D)>(&self, cluster: usize, synch_handle: &mut SynchHandle<T>, allocator: &mut A, apply: Apply, state: &State, data: &mut D)
+	{
+		let combiner_lock = if self.cluster_count == 1
+		{
+			None
+		}
+		else
+		{
+			Some(&self.combiner_lock)
+		};
+		
+		S::synch_apply(self.cluster(cluster), synch_handle, allocator, apply, state, data, combiner_lock, &self.help_bound)
+	}
+	
+	#[inline(always)]
+	unsafe fn synch_init<A: Allocator>(&mut self, allocator: &mut A)
+	{
+		for cluster in 0 .. self.cluster_count
+		{
+			write(self.clusters.get().add(cluster), UnsafeCell::new(S::new_synch(allocator)))
+		}
+	}
+	
+	#[inline(always)]
+	fn cluster(&self, cluster: usize) -> &UnsafeCell<Synch<T>>
+	{
+		unsafe { &* self.clusters.get().add(cluster) }
+	}
+}
@@ -0,0 +1,66 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// The maximum number of requests a combining thread applies, including its own, before handing over to another thread.
+/// A small bound limits how long any one thread spends combining, which favours latency; a large bound favours throughput.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum HelpBound
+{
+	/// Always the same bound; must not be zero.
+	Fixed(usize),
+	
+	/// A bound, starting at `HelpBound::DEFAULT` (or the nearest of `minimum` and `maximum`), that is tuned after each combining pass.
+	/// If a pass took longer than `target_hold_time` the bound is halved; if it applied as many requests as the bound allowed it is increased.
+	Adaptive
+	{
+		/// The smallest the bound can become; must not be zero.
+		minimum: usize,
+		
+		/// The largest the bound can become; must not be less than `minimum`.
+		maximum: usize,
+		
+		/// How long a combining thread should spend applying requests before handing over.
+		target_hold_time: Duration,
+	},
+}
+
+impl Default for HelpBound
+{
+	#[inline(always)]
+	fn default() -> Self
+	{
+		HelpBound::Fixed(HelpBound::DEFAULT)
+	}
+}
+
+impl HelpBound
+{
+	/// The bound used unless configured otherwise.
+	pub const DEFAULT: usize = 256;
+	
+	#[inline(always)]
+	fn assert_valid(&self)
+	{
+		match *self
+		{
+			HelpBound::Fixed(bound) => assert_ne!(bound, 0, "help bound can not be zero"),
+			
+			HelpBound::Adaptive { minimum, maximum, .. } =>
+			{
+				assert_ne!(minimum, 0, "minimum help bound can not be zero");
+				assert!(minimum <= maximum, "minimum help bound can not exceed maximum help bound")
+			}
+		}
+	}
+	
+	#[inline(always)]
+	fn initial(&self) -> usize
+	{
+		match *self
+		{
+			HelpBound::Fixed(bound) => bound,
+			HelpBound::Adaptive { minimum, maximum, .. } => HelpBound::DEFAULT.max(minimum).min(maximum),
+		}
+	}
+}
@@ -4,7 +4,7 @@
 
 trait IsNotNull
 {
-	#[inline(always)]
+	#[allow(clippy::wrong_self_convention)]
 	fn is_not_null(self) -> bool;
 }
 
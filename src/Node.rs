@@ -6,33 +6,18 @@
 #[repr(C)]
 struct Node<T>
 {
-	next: *mut Node<T>, // TODO: CACHE_ALIGNED
-	data: NonNull<T>, // except the dummy node's data can be null
+	// Nodes are allocated cache line aligned.
+	next: OffsetPointer<Node<T>>,
+	
+	// The number of nodes enqueued before and including this one; set by the enqueue combiner before the node is linked.
+	// The dummy node's is that of the last node dequeued, so it is the number of nodes ever dequeued.
+	index: u64,
+	
+	data: MaybeUninit<T>, // except the dummy node's data is never initialized
 }
 
 impl<T> Node<T>
 {
-	#[inline(always)]
-	fn clearing_queue_drop<A: Allocator, FreeData: Fn(NonNull<T>)>(this: NonNull<Self>, allocator: &mut A, free_data: &FreeData)
-	{
-		let x = unsafe { this.as_ref() };
-		
-		let next = x.next;
-		if next.is_not_null()
-		{
-			Self::clearing_queue_drop(unsafe { NonNull::new_unchecked(next) }, allocator, free_data);
-		}
-		
-		// dummy_node is created with a dangling data pointer
-		let data = x.data;
-		if data.as_ptr() != NonNull::dangling().as_ptr()
-		{
-			free_data(data);
-		}
-		
-		Self::free_after_drop(this, allocator)
-	}
-	
 	#[inline(always)]
 	fn free_after_drop<A: Allocator>(this: NonNull<Self>, allocator: &mut A)
 	{
@@ -45,9 +30,8 @@ impl<T> Node<T>
 		let mut dummy = allocator.align_malloc_cache_line_size();
 		{
 			let dummy: &mut Self = dummy.as_mut();
-			// This data is believed to be always overwritten...
-			write(&mut dummy.data, NonNull::dangling());
-			write(&mut dummy.next, null_mut());
+			write(&mut dummy.next, OffsetPointer::null());
+			write(&mut dummy.index, 0);
 		}
 		dummy
 	}
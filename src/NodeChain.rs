@@ -0,0 +1,113 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// A chain of nodes, each owning its data, that is not (or is no longer) linked into a queue.
+// The last node's `next` is null.
+#[derive(Debug)]
+struct NodeChain<T>
+{
+	first: NonNull<Node<T>>,
+	last: NonNull<Node<T>>,
+	
+	// Never zero.
+	length: usize,
+}
+
+impl<T> NodeChain<T>
+{
+	#[inline(always)]
+	fn single(node: NonNull<Node<T>>) -> Self
+	{
+		Self
+		{
+			first: node,
+			last: node,
+			length: 1,
+		}
+	}
+	
+	// Returns `None` if `batch` is empty.
+	// If `batch` panics, the nodes created so far are leaked.
+	#[inline(always)]
+	fn new<A: Allocator, I: IntoIterator<Item = T>>(handle: &mut PerQueueThreadHandleInternal<T, A>, batch: I) -> Option<Self>
+	{
+		let mut batch = batch.into_iter();
+		
+		let mut chain = Self::single(handle.new_node(batch.next()?));
+		for data in batch
+		{
+			chain.push(handle.new_node(data))
+		}
+		Some(chain)
+	}
+	
+	// `node`'s `next` must be null.
+	#[inline(always)]
+	fn push(&mut self, node: NonNull<Node<T>>)
+	{
+		unsafe { self.last.as_mut().next.set(node.as_ptr()) };
+		self.last = node;
+		self.length += 1;
+	}
+	
+	// Keeps the first `length` nodes and returns the rest.
+	// `length` must be greater than zero and less than `self.length`.
+	#[inline(always)]
+	fn split_off(&mut self, length: usize) -> Self
+	{
+		debug_assert!(length > 0 && length < self.length, "length out of range");
+		
+		let mut last = self.first;
+		for _ in 1 .. length
+		{
+			last = unsafe { last.as_ref().next.get_non_null() };
+		}
+		
+		let remainder = Self
+		{
+			first: unsafe { last.as_ref().next.get_non_null() },
+			last: self.last,
+			length: self.length - length,
+		};
+		
+		unsafe { last.as_mut().next.set(null_mut()) };
+		self.last = last;
+		self.length = length;
+		
+		remainder
+	}
+	
+	#[inline(always)]
+	fn for_each_node<F: FnMut(NonNull<Node<T>>)>(&self, mut f: F)
+	{
+		let mut node = self.first;
+		for index in 0 .. self.length
+		{
+			f(node);
+			if index + 1 < self.length
+			{
+				node = unsafe { node.as_ref().next.get_non_null() }
+			}
+		}
+	}
+	
+	// Moves the data out of each node, in order, recycling the nodes.
+	#[inline(always)]
+	fn into_data<A: Allocator, E: Extend<T>>(self, handle: &mut PerQueueThreadHandleInternal<T, A>, into: &mut E)
+	{
+		let mut node = self.first.as_ptr();
+		for _ in 0 .. self.length
+		{
+			let current = unsafe { NonNull::new_unchecked(node) };
+			let data = unsafe
+			{
+				let current = current.as_ref();
+				node = current.next.get();
+				read(current.data.as_ptr())
+			};
+			handle.recycle_node(current);
+			into.extend(Some(data))
+		}
+	}
+}
@@ -0,0 +1,103 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// A link to a `T` inside memory that comes from an allocator.
+// With the `offset-pointers` feature, it is stored as an offset from its own address, so that the memory can be mapped at a different address, eg a memory-mapped file reopened by another process; otherwise, it is a pointer.
+// Consequently, a link that is not null must never be moved; it is only ever set in place.
+// Null is zero either way.
+#[repr(transparent)]
+struct OffsetPointer<T>
+{
+	#[cfg(feature = "offset-pointers")] offset: isize,
+	#[cfg(not(feature = "offset-pointers"))] pointer: *mut T,
+	marker: PhantomData<*mut T>,
+}
+
+impl<T> Debug for OffsetPointer<T>
+{
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result
+	{
+		f.debug_tuple("OffsetPointer").field(&self.get()).finish()
+	}
+}
+
+impl<T> OffsetPointer<T>
+{
+	#[inline(always)]
+	fn null() -> Self
+	{
+		Self
+		{
+			#[cfg(feature = "offset-pointers")] offset: 0,
+			#[cfg(not(feature = "offset-pointers"))] pointer: null_mut(),
+			marker: PhantomData,
+		}
+	}
+	
+	// Result can be null
+	#[inline(always)]
+	fn get(&self) -> *mut T
+	{
+		#[cfg(feature = "offset-pointers")]
+		{
+			Self::from_offset(self as *const Self as usize, self.offset)
+		}
+		
+		#[cfg(not(feature = "offset-pointers"))]
+		{
+			self.pointer
+		}
+	}
+	
+	// Must not be null.
+	#[inline(always)]
+	unsafe fn get_non_null(&self) -> NonNull<T>
+	{
+		NonNull::new_unchecked(self.get())
+	}
+	
+	#[inline(always)]
+	fn set(&mut self, pointer: *mut T)
+	{
+		#[cfg(feature = "offset-pointers")]
+		{
+			self.offset = Self::to_offset(self as *const Self as usize, pointer)
+		}
+		
+		#[cfg(not(feature = "offset-pointers"))]
+		{
+			self.pointer = pointer
+		}
+	}
+	
+	// The offset of `pointer` from `address`, which is never zero unless `pointer` is null, as nothing links to itself.
+	#[cfg(feature = "offset-pointers")]
+	#[inline(always)]
+	fn to_offset(address: usize, pointer: *mut T) -> isize
+	{
+		if pointer.is_null()
+		{
+			0
+		}
+		else
+		{
+			(pointer as usize).wrapping_sub(address) as isize
+		}
+	}
+	
+	#[cfg(feature = "offset-pointers")]
+	#[inline(always)]
+	fn from_offset(address: usize, offset: isize) -> *mut T
+	{
+		if offset == 0
+		{
+			null_mut()
+		}
+		else
+		{
+			address.wrapping_add(offset as usize) as *mut T
+		}
+	}
+}
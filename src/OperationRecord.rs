@@ -0,0 +1,148 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// Records the last single enqueue or dequeue of the `PerQueueThreadHandle` using it, in the queue's memory, so that after a crash it can be determined whether the operation took effect, and what it returned.
+// An operation takes effect when the node it enqueues or dequeues is linked to `tail` or unlinked from `head`; the combiner sets `index` before that happens.
+#[derive(Debug)]
+#[repr(C)]
+struct OperationRecord<T>
+{
+	// Whether a `PerQueueThreadHandle` is using this record; not valid after the queue is reopened, so reset then.
+	in_use: AtomicBool,
+	
+	// One of `NONE`, `ENQUEUE` or `DEQUEUE`.
+	operation: u8,
+	
+	// Whether `data` holds the data of an enqueue that did not take effect; until then, it is in `node`.
+	has_data: bool,
+	
+	// The index of the node enqueued or dequeued (see `Node.index`), set by the combiner; `NOT_APPLIED` if the operation has not, or did not, take effect.
+	index: u64,
+	
+	// The node an enqueue enqueues.
+	node: OffsetPointer<Node<T>>,
+	
+	// The data a dequeue that took effect dequeued, or the data of an enqueue that did not take effect.
+	data: MaybeUninit<T>,
+}
+
+impl<T> OperationRecord<T>
+{
+	const NONE: u8 = 0;
+	
+	const ENQUEUE: u8 = 1;
+	
+	const DEQUEUE: u8 = 2;
+	
+	const NOT_APPLIED: u64 = u64::MAX;
+	
+	#[inline(always)]
+	unsafe fn initialize(this: *mut Self)
+	{
+		write(&mut (*this).in_use, AtomicBool::new(false));
+		write(&mut (*this).operation, Self::NONE);
+		write(&mut (*this).has_data, false);
+		write(&mut (*this).index, Self::NOT_APPLIED);
+		write(&mut (*this).node, OffsetPointer::null());
+	}
+	
+	// Panics if the record is already in use.
+	#[inline(always)]
+	fn acquire(&self)
+	{
+		assert!(!self.in_use.swap(true, Acquire), "operation record is already in use by a per-thread handle");
+	}
+	
+	#[inline(always)]
+	fn release(&self)
+	{
+		self.in_use.store(false, Release)
+	}
+	
+	// Called before an operation is requested; `node` is null for a dequeue.
+	#[inline(always)]
+	fn announce(&mut self, operation: u8, node: *mut Node<T>)
+	{
+		self.operation = operation;
+		self.has_data = false;
+		self.index = Self::NOT_APPLIED;
+		self.node.set(node)
+	}
+	
+	// Only ever called by the enqueue combiner, before the enqueue takes effect.
+	#[inline(always)]
+	fn enqueuing(&mut self, index: u64)
+	{
+		self.index = index
+	}
+	
+	// Only ever called by the dequeue combiner, before the dequeue of `node` takes effect.
+	#[inline(always)]
+	fn dequeuing(&mut self, node: NonNull<Node<T>>)
+	{
+		let node = unsafe { node.as_ref() };
+		unsafe { copy_nonoverlapping(node.data.as_ptr(), self.data.as_mut_ptr(), 1) };
+		self.index = node.index
+	}
+	
+	// Copies the data of an enqueue that did not take effect out of its node, before the node is reused.
+	#[inline(always)]
+	fn not_enqueued(&mut self)
+	{
+		debug_assert_eq!(self.operation, Self::ENQUEUE, "not an enqueue");
+		
+		self.index = Self::NOT_APPLIED;
+		unsafe { copy_nonoverlapping(self.node.get_non_null().as_ref().data.as_ptr(), self.data.as_mut_ptr(), 1) };
+		self.has_data = true
+	}
+	
+	// Decides whether an operation in progress when the queue's process exited took effect, given the indices of the nodes at `head` and `tail`.
+	// Returns the node of an enqueue that did not take effect, which is no longer needed.
+	#[inline(always)]
+	fn recover(&mut self, head_index: u64, tail_index: u64) -> Option<NonNull<Node<T>>>
+	{
+		match self.operation
+		{
+			Self::ENQUEUE if !self.has_data =>
+			{
+				if self.index != Self::NOT_APPLIED && self.index <= tail_index
+				{
+					None
+				}
+				else
+				{
+					self.not_enqueued();
+					Some(unsafe { self.node.get_non_null() })
+				}
+			}
+			
+			Self::DEQUEUE =>
+			{
+				if self.index != Self::NOT_APPLIED && self.index > head_index
+				{
+					self.index = Self::NOT_APPLIED
+				}
+				None
+			}
+			
+			_ => None,
+		}
+	}
+	
+	// What the last operation did; an enqueue neither applied nor with its data kept by `not_enqueued()` is still in progress.
+	#[inline(always)]
+	fn recorded(&self) -> RecordedOperation<T> where T: Copy
+	{
+		let applied = self.index != Self::NOT_APPLIED;
+		match self.operation
+		{
+			Self::ENQUEUE if applied => RecordedOperation::Enqueued,
+			Self::ENQUEUE if self.has_data => RecordedOperation::NotEnqueued(unsafe { self.data.assume_init() }),
+			Self::ENQUEUE => RecordedOperation::InProgress,
+			Self::DEQUEUE if applied => RecordedOperation::Dequeued(unsafe { self.data.assume_init() }),
+			Self::DEQUEUE => RecordedOperation::NotDequeued,
+			_ => RecordedOperation::None,
+		}
+	}
+}
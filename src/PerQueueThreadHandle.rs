@@ -4,37 +4,131 @@
 
 /// This structure is allocated for each thread that wants to access a queue.
 #[derive(Debug)]
-pub struct PerQueueThreadHandle<'queue, T: 'queue, A: 'queue + Allocator>(&'queue CcQueue<T, A>, NonNull<PerQueueThreadHandleInternal<T, A>>);
+pub struct PerQueueThreadHandle<'queue, T: 'queue, A: 'queue + Allocator, S: 'queue + SynchStrategy = CcSynchStrategy>(&'queue CcQueue<T, A, S>, NonNull<PerQueueThreadHandleInternal<T, A>>);
 
-impl<'queue, T, A: Allocator> Drop for PerQueueThreadHandle<'queue, T, A>
+impl<'queue, T, A: Allocator, S: SynchStrategy> Drop for PerQueueThreadHandle<'queue, T, A, S>
 {
 	#[inline(always)]
 	fn drop(&mut self)
 	{
-		let pointer = self.1;
-		unsafe { drop_in_place(pointer.as_ptr()) }
-		PerQueueThreadHandleInternal::free_after_drop(pointer);
+		// A forked child's copy of the handle belongs to the parent.
+		if !self.0.per_queue_thread_handle_internals.is_forked()
+		{
+			PerQueueThreadHandleInternal::drop_and_free(self.1)
+		}
 	}
 }
 
-impl<'queue, T, A: Allocator> PerQueueThreadHandle<'queue, T, A>
+impl<'queue, T, A: Allocator, S: SynchStrategy> PerQueueThreadHandle<'queue, T, A, S>
 {
 	/// Enqueue data.
+	/// The data is moved into the queue and is owned by it until dequeued.
+	/// If the queue is bounded, parks the thread until there is space.
+	/// If the queue is closed, the data is returned.
 	#[inline(always)]
-	pub fn enqueue(&mut self, data: NonNull<T>)
+	pub fn enqueue(&mut self, data: T) -> Result<(), T>
 	{
-		let queue = unsafe { (self.0).0.as_ref() };
+		let queue = self.0;
 		
-		queue.enqueue(self.handle(), data)
+		queue.enqueue_using(self.handle(), data)
+	}
+	
+	/// Enqueue data, unless the queue is full or closed, in which case the data is returned.
+	/// The data is moved into the queue and is owned by it until dequeued.
+	/// An unbounded queue is never full.
+	#[inline(always)]
+	pub fn try_enqueue(&mut self, data: T) -> Result<(), EnqueueError<T>>
+	{
+		let queue = self.0;
+		
+		queue.try_enqueue_using(self.handle(), data)
+	}
+	
+	/// Enqueue a batch of data, in order.
+	/// The batch is enqueued using one combining request, rather than one per item.
+	/// If the queue is bounded, as much of the batch as there is space for is enqueued, parking the thread until there is space for the rest.
+	/// If the queue is closed, the data not yet enqueued is returned.
+	#[inline(always)]
+	pub fn enqueue_batch<I: IntoIterator<Item = T>>(&mut self, batch: I) -> Result<(), Vec<T>>
+	{
+		let queue = self.0;
+		
+		queue.enqueue_batch_using(self.handle(), batch)
 	}
 	
 	/// Dequeue data.
+	/// Ownership of the data is moved out of the queue to the caller.
+	/// Returns `DequeueError::Closed` rather than `DequeueError::Empty` once the queue is closed and has been drained.
+	#[inline(always)]
+	pub fn dequeue(&mut self) -> Result<T, DequeueError>
+	{
+		let queue = self.0;
+		
+		queue.dequeue_using(self.handle())
+	}
+	
+	/// Dequeue up to `maximum` items of data, in order, appending them to `into`.
+	/// The batch is dequeued using one combining request, rather than one per item.
+	/// Returns the number of items dequeued, which is only zero if `maximum` is zero.
+	/// Returns `DequeueError::Closed` rather than `DequeueError::Empty` once the queue is closed and has been drained.
+	#[inline(always)]
+	pub fn dequeue_batch(&mut self, maximum: usize, into: &mut Vec<T>) -> Result<usize, DequeueError>
+	{
+		let queue = self.0;
+		
+		queue.dequeue_batch_using(self.handle(), maximum, into)
+	}
+	
+	/// Dequeue data, parking the thread until data is available if the queue is empty.
+	/// Ownership of the data is moved out of the queue to the caller.
+	/// Returns `None` once the queue is closed and has been drained.
 	#[inline(always)]
-	pub fn dequeue(&mut self) -> Option<NonNull<T>>
+	pub fn dequeue_wait(&mut self) -> Option<T>
 	{
-		let queue = unsafe { (self.0).0.as_ref() };
+		let queue = self.0;
 		
-		queue.dequeue(self.handle())
+		queue.dequeue_wait_using(self.handle())
+	}
+	
+	/// Dequeue data, parking the thread until data is available if the queue is empty.
+	/// Returns `DequeueError::TimedOut` if no data became available before `timeout` elapsed, or `DequeueError::Closed` once the queue is closed and has been drained.
+	/// Ownership of the data is moved out of the queue to the caller.
+	#[inline(always)]
+	pub fn dequeue_timeout(&mut self, timeout: Duration) -> Result<T, DequeueError>
+	{
+		let queue = self.0;
+		
+		queue.dequeue_timeout_using(self.handle(), timeout)
+	}
+	
+	/// Dequeue data, parking the thread until data is available if the queue is empty.
+	/// Returns `DequeueError::TimedOut` if no data became available before `deadline`, or `DequeueError::Closed` once the queue is closed and has been drained.
+	/// Ownership of the data is moved out of the queue to the caller.
+	#[inline(always)]
+	pub fn dequeue_deadline(&mut self, deadline: Instant) -> Result<T, DequeueError>
+	{
+		let queue = self.0;
+		
+		queue.dequeue_deadline_using(self.handle(), deadline)
+	}
+	
+	/// Close the queue.
+	/// Subsequent enqueues fail, returning their data; data already enqueued can still be dequeued, after which dequeues report `DequeueError::Closed`.
+	/// Wakes all parked threads and registered wakers.
+	/// Closing an already closed queue does nothing.
+	#[inline(always)]
+	pub fn close(&mut self)
+	{
+		let queue = self.0;
+		
+		queue.close_using(self.handle())
+	}
+	
+	/// The cluster this handle combines in, which is less than `CcQueue.clusters()`.
+	#[inline(always)]
+	pub fn cluster(&self) -> usize
+	{
+		unsafe { (self.1).as_ref() }.cluster
 	}
 	
 	#[inline(always)]
@@ -2,19 +2,29 @@
 // Copyright © 2017 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
 
 
-// TODO: DOUBLE_CACHE_ALIGNED
+// Padded so that nothing written by other threads shares its cache lines.
 #[derive(Debug)]
 #[repr(C)]
 struct PerQueueThreadHandleInternal<T, A: Allocator>
 {
-	enq: SynchHandle<T>,
-	deq: SynchHandle<T>,
+	enq: DoubleCacheAligned<SynchHandle<T>>,
+	deq: DoubleCacheAligned<SynchHandle<T>>,
+	
+	// The H-Synch cluster this handle combines in.
+	cluster: usize,
 	
 	// Used for object pooling; recycles dequeue'd Node<T> to avoid additional calls to `allocate_next_node()`.
 	// Can be null
 	// If not null, is **never** fully initialized.
 	next: *mut Node<T>,
 	
+	// The operation record of a recoverable per-thread handle, which it has acquired; its single enqueues and dequeues are recorded in it.
+	record: Option<NonNull<OperationRecord<T>>>,
+	
+	// Requests are made from here, rather than from the stack, so that a combiner in another process sharing the queue can apply them.
+	enqueue_request: MaybeUninit<EnqueueRequest<T>>,
+	dequeue_request: MaybeUninit<DequeueRequest<T>>,
+	
 	allocator: A,
 }
 
@@ -23,10 +33,18 @@ impl<T, A: Allocator> Drop for PerQueueThreadHandleInternal<T, A>
 	#[inline(always)]
 	fn drop(&mut self)
 	{
+		self.enq.free_nodes(&mut self.allocator);
+		self.deq.free_nodes(&mut self.allocator);
+		
 		if self.next.is_not_null()
 		{
 			self.allocator.free_cache_line_size(unsafe { NonNull::new_unchecked(self.next) });
 		}
+		
+		if let Some(record) = self.record
+		{
+			unsafe { record.as_ref() }.release()
+		}
 	}
 }
 
@@ -44,26 +62,82 @@ impl<T, A: Allocator> PerQueueThreadHandleInternal<T, A>
 		allocator.align_malloc_cache_line_size()
 	}
 	
+	// Takes a node from the pool, if there is one, and moves `data` into it.
 	#[inline(always)]
-	fn free_after_drop(this: NonNull<Self>)
+	fn new_node(&mut self, data: T) -> NonNull<Node<T>>
 	{
-		HeapAllocator.free_page_size(this)
+		let node = self.next;
+		
+		unsafe
+		{
+			// Object pooling
+			let mut node = if node.is_not_null()
+			{
+				write(&mut self.next, null_mut());
+				NonNull::new_unchecked(node)
+			}
+			else
+			{
+				self.allocate_next_node()
+			};
+			
+			{
+				let node = node.as_mut();
+				write(node.data.as_mut_ptr(), data);
+				write(&mut node.next, OffsetPointer::null());
+			}
+			
+			node
+		}
+	}
+	
+	// Returns a node, whose data has been moved out, to the pool, or frees it if the pool is full.
+	#[inline(always)]
+	fn recycle_node(&mut self, node: NonNull<Node<T>>)
+	{
+		// Object pooling
+		if self.next.is_not_null()
+		{
+			Node::free_after_drop(node, &mut self.allocator)
+		}
+		else
+		{
+			self.next = node.as_ptr()
+		}
+	}
+	
+	#[inline(always)]
+	fn free_after_drop(this: NonNull<Self>, mut allocator: A)
+	{
+		allocator.free_page_size(this)
+	}
+	
+	#[inline(always)]
+	fn drop_and_free(this: NonNull<Self>)
+	{
+		let allocator = unsafe { this.as_ref() }.allocator.clone();
+		unsafe { drop_in_place(this.as_ptr()) }
+		Self::free_after_drop(this, allocator)
 	}
 	
 	// happens once per-thread
 	#[inline(always)]
-	fn new(mut allocator: A) -> NonNull<Self>
+	fn new(mut allocator: A, cluster: usize, record: Option<NonNull<OperationRecord<T>>>) -> NonNull<Self>
 	{
-		let mut handle = HeapAllocator.align_malloc_page_size();
+		let mut handle = allocator.align_malloc_page_size();
 		unsafe
 		{
 			let handle: &mut Self = handle.as_mut();
 			
-			handle.enq.ccsynch_handle_init();
-			handle.deq.ccsynch_handle_init();
+			handle.enq.ccsynch_handle_init(&mut allocator);
+			handle.deq.ccsynch_handle_init(&mut allocator);
+			
+			write(&mut handle.cluster, cluster);
 			
 			write(&mut handle.next, Self::allocate_next_node_(&mut allocator).as_ptr());
 			
+			write(&mut handle.record, record);
+			
 			write(&mut handle.allocator, allocator);
 		}
 		handle
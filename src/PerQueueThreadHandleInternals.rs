@@ -0,0 +1,95 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// Tracks the per-thread handles created implicitly for a queue and stored in thread-local storage.
+// Each is freed either when its thread exits or when the queue is dropped, whichever happens first.
+#[derive(Debug)]
+struct PerQueueThreadHandleInternals<T, A: Allocator>
+{
+	handles: Mutex<Vec<NonNull<PerQueueThreadHandleInternal<T, A>>>>,
+	
+	// If the queue is shared between processes, `Forks::count()` when it was created or attached; the handles do not belong to a forked child, even though it has copies of them.
+	forks: Option<usize>,
+}
+
+trait ReleasePerQueueThreadHandleInternal
+{
+	// Called when a thread exits.
+	// Does nothing if the queue has already freed the handle.
+	fn release(&self, handle: NonNull<u8>);
+}
+
+impl<T, A: Allocator> ReleasePerQueueThreadHandleInternal for PerQueueThreadHandleInternals<T, A>
+{
+	#[inline(always)]
+	fn release(&self, handle: NonNull<u8>)
+	{
+		if self.is_forked()
+		{
+			return
+		}
+		
+		let handle = handle.cast();
+		
+		let mut handles = self.handles();
+		if let Some(index) = handles.iter().position(|candidate| *candidate == handle)
+		{
+			handles.swap_remove(index);
+			PerQueueThreadHandleInternal::drop_and_free(handle)
+		}
+	}
+}
+
+impl<T, A: Allocator> PerQueueThreadHandleInternals<T, A>
+{
+	#[inline(always)]
+	fn new(shared_between_processes: bool) -> Self
+	{
+		Self
+		{
+			handles: Mutex::new(Vec::new()),
+			forks: if shared_between_processes { Some(Forks::start()) } else { None },
+		}
+	}
+	
+	// Whether this process is a forked child of the one that created or attached the queue.
+	#[inline(always)]
+	fn is_forked(&self) -> bool
+	{
+		match self.forks
+		{
+			None => false,
+			Some(forks) => forks != Forks::count(),
+		}
+	}
+	
+	#[inline(always)]
+	fn new_per_queue_thread_handle_internal(&self, allocator: A, cluster: usize) -> NonNull<PerQueueThreadHandleInternal<T, A>>
+	{
+		let handle = PerQueueThreadHandleInternal::new(allocator, cluster, None);
+		self.handles().push(handle);
+		handle
+	}
+	
+	// Called when the queue is dropped.
+	#[inline(always)]
+	fn free_all(&self)
+	{
+		if self.is_forked()
+		{
+			return
+		}
+		
+		for handle in self.handles().drain(..)
+		{
+			PerQueueThreadHandleInternal::drop_and_free(handle)
+		}
+	}
+	
+	#[inline(always)]
+	fn handles(&self) -> MutexGuard<'_, Vec<NonNull<PerQueueThreadHandleInternal<T, A>>>>
+	{
+		self.handles.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+}
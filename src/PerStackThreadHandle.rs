@@ -0,0 +1,45 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// This structure is allocated for each thread that wants to access a stack.
+#[derive(Debug)]
+pub struct PerStackThreadHandle<'stack, T: 'stack, A: 'stack + Allocator>(&'stack CcStack<T, A>, NonNull<PerStackThreadHandleInternal<T, A>>);
+
+impl<'stack, T, A: Allocator> Drop for PerStackThreadHandle<'stack, T, A>
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		PerStackThreadHandleInternal::drop_and_free(self.1)
+	}
+}
+
+impl<'stack, T, A: Allocator> PerStackThreadHandle<'stack, T, A>
+{
+	/// Push data.
+	/// The data is moved into the stack and is owned by it until popped.
+	#[inline(always)]
+	pub fn push(&mut self, data: T)
+	{
+		let stack = self.0;
+		
+		stack.stack_internal().push(self.handle(), data)
+	}
+	
+	/// Pop the most recently pushed data, if any.
+	/// Ownership of the data is moved out of the stack to the caller.
+	#[inline(always)]
+	pub fn pop(&mut self) -> Option<T>
+	{
+		let stack = self.0;
+		
+		stack.stack_internal().pop(self.handle())
+	}
+	
+	#[inline(always)]
+	fn handle(&mut self) -> &mut PerStackThreadHandleInternal<T, A>
+	{
+		unsafe { (self.1).as_mut() }
+	}
+}
@@ -0,0 +1,100 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// Padded so that nothing written by other threads shares its cache lines.
+#[derive(Debug)]
+#[repr(C)]
+struct PerStackThreadHandleInternal<T, A: Allocator>
+{
+	synch: DoubleCacheAligned<SynchHandle<T>>,
+	
+	// Used for object pooling; recycles pop'd Node<T> to avoid additional calls to `allocate_next_node()`.
+	// Can be null
+	// If not null, is **never** fully initialized.
+	next: *mut Node<T>,
+	
+	allocator: A,
+}
+
+impl<T, A: Allocator> Drop for PerStackThreadHandleInternal<T, A>
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		self.synch.free_nodes(&mut self.allocator);
+		
+		if self.next.is_not_null()
+		{
+			self.allocator.free_cache_line_size(unsafe { NonNull::new_unchecked(self.next) });
+		}
+	}
+}
+
+impl<T, A: Allocator> PerStackThreadHandleInternal<T, A>
+{
+	// Takes a node from the pool, if there is one, and moves `data` into it.
+	#[inline(always)]
+	fn new_node(&mut self, data: T) -> NonNull<Node<T>>
+	{
+		let node = self.next;
+		
+		unsafe
+		{
+			// Object pooling
+			let mut node = if node.is_not_null()
+			{
+				write(&mut self.next, null_mut());
+				NonNull::new_unchecked(node)
+			}
+			else
+			{
+				self.allocator.align_malloc_cache_line_size()
+			};
+			
+			write(node.as_mut().data.as_mut_ptr(), data);
+			
+			node
+		}
+	}
+	
+	// Returns a node, whose data has been moved out, to the pool, or frees it if the pool is full.
+	#[inline(always)]
+	fn recycle_node(&mut self, node: NonNull<Node<T>>)
+	{
+		// Object pooling
+		if self.next.is_not_null()
+		{
+			Node::free_after_drop(node, &mut self.allocator)
+		}
+		else
+		{
+			self.next = node.as_ptr()
+		}
+	}
+	
+	#[inline(always)]
+	fn drop_and_free(this: NonNull<Self>)
+	{
+		let mut allocator = unsafe { this.as_ref() }.allocator.clone();
+		unsafe { drop_in_place(this.as_ptr()) }
+		allocator.free_page_size(this)
+	}
+	
+	#[inline(always)]
+	fn new(mut allocator: A) -> NonNull<Self>
+	{
+		let mut handle = allocator.align_malloc_page_size();
+		unsafe
+		{
+			let handle: &mut Self = handle.as_mut();
+			
+			handle.synch.ccsynch_handle_init(&mut allocator);
+			
+			write(&mut handle.next, null_mut());
+			
+			write(&mut handle.allocator, allocator);
+		}
+		handle
+	}
+}
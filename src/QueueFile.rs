@@ -0,0 +1,252 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// A queue in a file allocated from by an `allocators::MmapFileAllocator`, recorded as the file's root, opened without knowing the queue's type so that it can be inspected, or its tail repaired, whilst no process is using it.
+/// Its data is only available as bytes.
+/// The `cc-queue-inspect` binary is built on this.
+#[derive(Debug)]
+pub struct QueueFile
+{
+	allocator: MmapFileAllocator,
+	queue: NonNull<u8>,
+	writable: bool,
+}
+
+impl QueueFile
+{
+	/// Opens a file read-only (see `MmapFileAllocator::open_read_only()`).
+	///
+	/// Fails with `std::io::ErrorKind::InvalidData` if the file has no root, or its root is not a queue of a version, and with links stored in the way, that this build supports (see `QueueHeaderError`).
+	#[inline(always)]
+	pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self>
+	{
+		Self::new(MmapFileAllocator::open_read_only(path)?, false)
+	}
+	
+	/// Opens a file read-write, as `MmapFileAllocator::recover()`, so that `repair_tail()` can be used; no other process may have it open.
+	///
+	/// Fails as `open()`.
+	#[inline(always)]
+	pub fn open_for_repair<P: AsRef<Path>>(path: P) -> io::Result<Self>
+	{
+		Self::new(MmapFileAllocator::recover(path)?, true)
+	}
+	
+	#[inline(always)]
+	fn new(allocator: MmapFileAllocator, writable: bool) -> io::Result<Self>
+	{
+		let queue = match allocator.root()
+		{
+			Some(queue) => queue,
+			None => return Err(io::Error::new(InvalidData, "file has no root")),
+		};
+		if !allocator.contains(queue, size_of::<QueueHeader>())
+		{
+			return Err(io::Error::new(InvalidData, QueueHeaderError::NotAQueue))
+		}
+		
+		let this = QueueFile
+		{
+			allocator,
+			queue,
+			writable,
+		};
+		
+		this.header().identify().map_err(|error| io::Error::new(InvalidData, error))?;
+		if !this.header_is_consistent()
+		{
+			return Err(io::Error::new(InvalidData, "queue's header is inconsistent"))
+		}
+		
+		Ok(this)
+	}
+	
+	// The file may be broken, or crafted, so nothing in the header is trusted.
+	// Every field read using the header must be aligned and in the file, and every node must be large enough for its link and data, as a node's link is read from any node in the file.
+	#[inline(always)]
+	fn header_is_consistent(&self) -> bool
+	{
+		let header = self.header();
+		
+		let mut fields_end: u64 = 0;
+		for &offset in [header.head_offset, header.tail_offset, header.enqueued_offset, header.dequeued_offset].iter()
+		{
+			if offset & (align_of::<usize>() as u64 - 1) != 0
+			{
+				return false
+			}
+			match offset.checked_add(size_of::<usize>() as u64)
+			{
+				Some(end) => fields_end = max(fields_end, end),
+				None => return false,
+			}
+		}
+		if fields_end > self.allocator.length() as u64 || !self.allocator.contains(self.queue, fields_end as usize)
+		{
+			return false
+		}
+		
+		match header.node_data_offset.checked_add(header.data_size)
+		{
+			Some(node_data_end) => header.node_size >= size_of::<usize>() as u64 && node_data_end <= header.node_size && header.node_size <= self.allocator.length() as u64,
+			None => false,
+		}
+	}
+	
+	/// The format version of the queue (see `QueueHeaderError::UnsupportedVersion`).
+	#[inline(always)]
+	pub fn version(&self) -> u64
+	{
+		self.header().version
+	}
+	
+	/// The offset of the queue's `head`, a link to the node before the first item, from the start of the queue (see `MmapFileAllocator::root()`), as recorded in its header.
+	/// A link is a pointer, or, with the `offset-pointers` feature, an offset from the link's own address.
+	#[inline(always)]
+	pub fn head_offset(&self) -> usize
+	{
+		self.header().head_offset as usize
+	}
+	
+	/// The offset of the queue's `tail`, a link to the node of the last item, from the start of the queue, as recorded in its header; see `head_offset()`.
+	#[inline(always)]
+	pub fn tail_offset(&self) -> usize
+	{
+		self.header().tail_offset as usize
+	}
+	
+	/// The size of the queue's data, `T`, in bytes.
+	#[inline(always)]
+	pub fn data_size(&self) -> usize
+	{
+		self.header().data_size as usize
+	}
+	
+	/// The alignment of the queue's data, `T`, in bytes.
+	#[inline(always)]
+	pub fn data_alignment(&self) -> usize
+	{
+		self.header().data_alignment as usize
+	}
+	
+	/// Walks the chain of nodes from the queue's `head`, checking that each link is to a node in the file and that no node is found twice, and compares its end with the queue's `tail` and counts.
+	#[inline(always)]
+	pub fn inspect(&self) -> QueueInspection<'_>
+	{
+		self.walk().0
+	}
+	
+	/// Makes the queue's `tail` the last node in the chain, and its counts agree with the number of items in the chain, as `CcQueue::recover()` does, writing them back to the file; returns whether either changed.
+	/// The queue can then be reopened using `CcQueue::from_raw()`, but only `CcQueue::recover()` decides whether the operations of recoverable per-thread handles took effect.
+	///
+	/// Fails with `std::io::ErrorKind::PermissionDenied` if opened with `open()` rather than `open_for_repair()`, and with `std::io::ErrorKind::InvalidData` if a link is broken, as the last node is then unknown.
+	#[inline(always)]
+	pub fn repair_tail(&mut self) -> io::Result<bool>
+	{
+		if !self.writable
+		{
+			return Err(io::Error::new(PermissionDenied, "queue file was opened read-only"))
+		}
+		
+		let (inspection, last) = self.walk();
+		if inspection.broken_link.is_some()
+		{
+			return Err(io::Error::new(InvalidData, "queue's chain of nodes is broken, so its last node is unknown"))
+		}
+		if inspection.is_consistent()
+		{
+			return Ok(false)
+		}
+		let length = inspection.items.len();
+		
+		let header = self.header();
+		let tail = self.field::<OffsetPointer<Node<()>>>(header.tail_offset);
+		let enqueued = self.field::<AtomicUsize>(header.enqueued_offset);
+		let dequeued = self.field::<AtomicUsize>(header.dequeued_offset);
+		unsafe
+		{
+			(*tail.as_ptr()).set(last);
+			enqueued.as_ref().store(length, Relaxed);
+			dequeued.as_ref().store(0, Relaxed);
+		}
+		
+		self.allocator.persist(tail, size_of::<OffsetPointer<Node<()>>>());
+		self.allocator.persist(enqueued, size_of::<AtomicUsize>());
+		self.allocator.persist(dequeued, size_of::<AtomicUsize>());
+		Ok(true)
+	}
+	
+	// Also returns the last node found.
+	// Only a node's link is read as a `Node<()>`, as it is first whatever the data.
+	#[inline(always)]
+	fn walk(&self) -> (QueueInspection<'_>, *mut Node<()>)
+	{
+		let header = self.header();
+		let node_size = header.node_size as usize;
+		
+		let mut items = Vec::new();
+		let mut found = HashSet::new();
+		let mut broken_link = None;
+		let mut last: *mut Node<()> = null_mut();
+		let mut link = unsafe { self.field::<OffsetPointer<Node<()>>>(header.head_offset).as_ref() }.get();
+		loop
+		{
+			if link.is_null()
+			{
+				if last.is_null()
+				{
+					broken_link = Some(BrokenLink::Dangling(0))
+				}
+				break
+			}
+			
+			let node = unsafe { NonNull::new_unchecked(link) };
+			if !self.allocator.contains(node, node_size)
+			{
+				broken_link = Some(BrokenLink::Dangling(items.len()));
+				break
+			}
+			if !found.insert(link as usize)
+			{
+				broken_link = Some(BrokenLink::Cycle(items.len()));
+				break
+			}
+			
+			// The first node is the dummy node, whose data is never initialized.
+			if !last.is_null()
+			{
+				items.push(unsafe { from_raw_parts((link as *const u8).add(header.node_data_offset as usize), header.data_size as usize) })
+			}
+			
+			last = link;
+			link = unsafe { node.as_ref() }.next.get();
+		}
+		
+		let tail = unsafe { self.field::<OffsetPointer<Node<()>>>(header.tail_offset).as_ref() }.get();
+		let enqueued = unsafe { self.field::<AtomicUsize>(header.enqueued_offset).as_ref() }.load(Relaxed);
+		let dequeued = unsafe { self.field::<AtomicUsize>(header.dequeued_offset).as_ref() }.load(Relaxed);
+		
+		let inspection = QueueInspection
+		{
+			items,
+			broken_link,
+			tail_is_last: broken_link.is_none() && tail == last,
+			recorded_length: enqueued.wrapping_sub(dequeued),
+		};
+		(inspection, last)
+	}
+	
+	#[inline(always)]
+	fn header(&self) -> &QueueHeader
+	{
+		unsafe { self.queue.cast::<QueueHeader>().as_ref() }
+	}
+	
+	// `offset` is one checked by `new()`.
+	#[inline(always)]
+	fn field<F>(&self, offset: u64) -> NonNull<F>
+	{
+		unsafe { NonNull::new_unchecked(self.queue.as_ptr().add(offset as usize)).cast() }
+	}
+}
@@ -0,0 +1,157 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// Identifies the memory at the start of a queue's allocation as a queue, and records the layout it was created with, so that a queue is only reopened (see `CcQueue::from_raw()`) as the same type by a compatible build.
+// Also records where a queue's links and counts are, so that it can be inspected without knowing its type (see `QueueFile`).
+// Its own layout is fixed; a change to it, or to how a queue is laid out other than by `T`, `S`, the allocator or a feature, needs a new `VERSION`.
+#[derive(Debug)]
+#[repr(C)]
+struct QueueHeader
+{
+	magic: [u8; 8],
+	version: u64,
+	data_size: u64,
+	data_alignment: u64,
+	node_size: u64,
+	node_alignment: u64,
+	node_data_offset: u64,
+	cache_line_size: u64,
+	page_size: u64,
+	pointer_size: u64,
+	
+	// Whether links are offsets rather than pointers (see `OffsetPointer`).
+	offset_pointers: u64,
+	
+	// Of fields in `QueueInternal`.
+	head_offset: u64,
+	tail_offset: u64,
+	enqueued_offset: u64,
+	dequeued_offset: u64,
+	
+	// Everything else that affects the layout of the queue, such as the synch strategy and features.
+	layout_hash: u64,
+}
+
+impl QueueHeader
+{
+	const MAGIC: [u8; 8] = *b"CCQUEUE\0";
+	
+	const VERSION: u64 = 1;
+	
+	#[inline(always)]
+	fn new<T, A: Allocator, S: SynchStrategy>() -> Self
+	{
+		QueueHeader
+		{
+			magic: Self::MAGIC,
+			version: Self::VERSION,
+			data_size: size_of::<T>() as u64,
+			data_alignment: align_of::<T>() as u64,
+			node_size: size_of::<Node<T>>() as u64,
+			node_alignment: align_of::<Node<T>>() as u64,
+			node_data_offset: offset_of!(Node<T>, data) as u64,
+			cache_line_size: CACHE_LINE_SIZE as u64,
+			page_size: A::PAGE_SIZE as u64,
+			pointer_size: size_of::<usize>() as u64,
+			offset_pointers: cfg!(feature = "offset-pointers") as u64,
+			head_offset: offset_of!(QueueInternal<T, A, S>, head) as u64,
+			tail_offset: offset_of!(QueueInternal<T, A, S>, tail) as u64,
+			enqueued_offset: offset_of!(QueueInternal<T, A, S>, enqueued) as u64,
+			dequeued_offset: offset_of!(QueueInternal<T, A, S>, dequeued) as u64,
+			layout_hash: Self::layout_hash::<T, A, S>(),
+		}
+	}
+	
+	#[inline(always)]
+	fn validate<T, A: Allocator, S: SynchStrategy>(&self) -> Result<(), QueueHeaderError>
+	{
+		self.identify()?;
+		
+		let expected = Self::new::<T, A, S>();
+		let fields =
+		[
+			("size of data", self.data_size, expected.data_size),
+			("alignment of data", self.data_alignment, expected.data_alignment),
+			("size of node", self.node_size, expected.node_size),
+			("alignment of node", self.node_alignment, expected.node_alignment),
+			("offset of node data", self.node_data_offset, expected.node_data_offset),
+			("cache line size", self.cache_line_size, expected.cache_line_size),
+			("page size", self.page_size, expected.page_size),
+			("offset of head", self.head_offset, expected.head_offset),
+			("offset of tail", self.tail_offset, expected.tail_offset),
+			("offset of enqueued count", self.enqueued_offset, expected.enqueued_offset),
+			("offset of dequeued count", self.dequeued_offset, expected.dequeued_offset),
+			("layout hash", self.layout_hash, expected.layout_hash),
+		];
+		Self::compare(&fields)
+	}
+	
+	// Checks what does not depend on the queue's type: the magic number and version before anything else, as the rest of a header with a different version may be laid out differently, then how links are stored.
+	#[inline(always)]
+	fn identify(&self) -> Result<(), QueueHeaderError>
+	{
+		if self.magic != Self::MAGIC
+		{
+			return Err(QueueHeaderError::NotAQueue)
+		}
+		
+		if self.version != Self::VERSION
+		{
+			return Err(QueueHeaderError::UnsupportedVersion(self.version))
+		}
+		
+		let fields =
+		[
+			("pointer size", self.pointer_size, size_of::<usize>() as u64),
+			("offset pointers", self.offset_pointers, cfg!(feature = "offset-pointers") as u64),
+		];
+		Self::compare(&fields)
+	}
+	
+	#[inline(always)]
+	fn compare(fields: &[(&'static str, u64, u64)]) -> Result<(), QueueHeaderError>
+	{
+		for &(field, found, expected) in fields.iter()
+		{
+			if found != expected
+			{
+				return Err(QueueHeaderError::LayoutMismatch { field, found, expected })
+			}
+		}
+		Ok(())
+	}
+	
+	// Once a queue is dropped, its memory is no longer a queue.
+	#[inline(always)]
+	fn invalidate(&mut self)
+	{
+		self.magic = [0; 8];
+	}
+	
+	// FNV-1a, which, unlike `std::hash::DefaultHasher`, is the same for every build and process.
+	#[inline(always)]
+	fn layout_hash<T, A: Allocator, S: SynchStrategy>() -> u64
+	{
+		let layout =
+		[
+			size_of::<QueueInternal<T, A, S>>() as u64,
+			align_of::<QueueInternal<T, A, S>>() as u64,
+			size_of::<OperationRecord<T>>() as u64,
+			cfg!(target_endian = "big") as u64,
+			S::IDENTIFIER,
+			cfg!(feature = "cache-padding") as u64,
+		];
+		
+		let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+		for value in layout.iter()
+		{
+			for byte in value.to_le_bytes().iter()
+			{
+				hash ^= *byte as u64;
+				hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+			}
+		}
+		hash
+	}
+}
@@ -0,0 +1,45 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// Why memory could not be reopened as a queue (see `CcQueue::from_raw()`, `CcQueue::recover()` and `CcQueue::attach()`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueueHeaderError
+{
+	/// The memory does not start with a queue's magic number, so is not a queue, or is one that has been dropped.
+	NotAQueue,
+	
+	/// The queue was created by a version of this crate that lays queues out differently.
+	UnsupportedVersion(u64),
+	
+	/// The queue was created with a different layout, eg for a different `T` or synch strategy, with different features, or for a different architecture.
+	LayoutMismatch
+	{
+		/// What differs, eg `size of data`.
+		field: &'static str,
+		
+		/// What the queue was created with.
+		found: u64,
+		
+		/// What this build would have created it with.
+		expected: u64,
+	},
+}
+
+impl Display for QueueHeaderError
+{
+	#[inline(always)]
+	fn fmt(&self, formatter: &mut Formatter) -> fmt::Result
+	{
+		match *self
+		{
+			QueueHeaderError::NotAQueue => write!(formatter, "memory is not a queue"),
+			QueueHeaderError::UnsupportedVersion(version) => write!(formatter, "queue has unsupported format version {}", version),
+			QueueHeaderError::LayoutMismatch { field, found, expected } => write!(formatter, "queue's {} is {}, but {} was expected", field, found, expected),
+		}
+	}
+}
+
+impl Error for QueueHeaderError
+{
+}
@@ -0,0 +1,31 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// What `QueueFile::inspect()` found in a queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueInspection<'a>
+{
+	/// The bytes of the data in each node, in the order they would be dequeued, up to the end of the chain of nodes or its first broken link.
+	pub items: Vec<&'a [u8]>,
+	
+	/// The first link in the chain of nodes that can not be followed, if any.
+	pub broken_link: Option<BrokenLink>,
+	
+	/// Whether the queue's `tail` is the last node in the chain; always false if a link is broken.
+	/// After a crash, `tail` may be stale (see `CcQueue::recover()` and `QueueFile::repair_tail()`).
+	pub tail_is_last: bool,
+	
+	/// The number of items the queue's counts of enqueued and dequeued data imply, which should be the number of `items`.
+	pub recorded_length: usize,
+}
+
+impl<'a> QueueInspection<'a>
+{
+	/// Whether the chain of nodes is whole, and agrees with the queue's `tail` and counts.
+	#[inline(always)]
+	pub fn is_consistent(&self) -> bool
+	{
+		self.broken_link.is_none() && self.tail_is_last && self.recorded_length == self.items.len()
+	}
+}
@@ -2,45 +2,97 @@
 // Copyright © 2017 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
 
 
-// TODO: DOUBLE_CACHE_ALIGNED
+// Everything written by enqueuers is kept apart from everything written by dequeuers.
 #[derive(Debug)]
 #[repr(C)]
-struct QueueInternal<T, A: Allocator>
+struct QueueInternal<T, A: Allocator, S: SynchStrategy>
 {
-	enq: UnsafeCell<Synch<T>>, // TODO: DOUBLE_CACHE_ALIGNED
-	deq: UnsafeCell<Synch<T>>, // TODO: DOUBLE_CACHE_ALIGNED
-	head: UnsafeCell<NonNull<Node<T>>>, // TODO: DOUBLE_CACHE_ALIGNED
-	tail: UnsafeCell<NonNull<Node<T>>>, // TODO: DOUBLE_CACHE_ALIGNED
+	// First, so that it can be checked before anything else is read.
+	header: QueueHeader,
+	
+	enq: DoubleCacheAligned<HSynch<T, S>>,
+	deq: DoubleCacheAligned<HSynch<T, S>>,
+	head: DoubleCacheAligned<UnsafeCell<OffsetPointer<Node<T>>>>,
+	tail: DoubleCacheAligned<UnsafeCell<OffsetPointer<Node<T>>>>,
 	allocator: UnsafeCell<A>,
+	
+	// Run on each item still in the queue when it is dropped.
+	destructor: fn(T),
+	
+	// `usize::MAX` if unbounded.
+	capacity: usize,
+	
+	// Only ever stored to by the enqueue combiner, so incremented without a read-modify-write.
+	// Wraps.
+	enqueued: DoubleCacheAligned<AtomicUsize>,
+	
+	// Only ever stored to by the dequeue combiner, so incremented without a read-modify-write.
+	// Wraps.
+	dequeued: DoubleCacheAligned<AtomicUsize>,
+	
+	// Only ever stored to by the enqueue combiner, after which nothing more is linked to `tail`.
+	closed: AtomicBool,
+	
+	// If durable, the nodes of a chain are written back before they are linked to `tail`, and `head` is written back before dequeued nodes are reused; `tail` and the counts are not, as `recover()` derives them.
+	// Operation records are written back before an operation is requested and before it takes effect.
+	durable: bool,
+	
+	// `record_count` of them, allocated from the queue's allocator; null if none.
+	records: OffsetPointer<CacheAligned<OperationRecord<T>>>,
+	
+	record_count: usize,
+	
+	// If shared between processes, `durable` is false, as writing back uses `allocator`, which belongs to the process that created the queue.
+	shared_between_processes: bool,
 }
 
-impl<T, A: Allocator> AllocatorOpened<A> for QueueInternal<T, A>
+impl<T, A: Allocator, S: SynchStrategy> AllocatorOpened<A> for QueueInternal<T, A, S>
 {
 	#[inline(always)]
 	fn allocator_opened(&mut self, allocator: A)
 	{
 		unsafe
 		{
-			Synch::ccsynch_init(&self.enq);
-			Synch::ccsynch_init(&self.deq);
-		
-			write(&mut self.allocator, UnsafeCell::new(allocator))
+			write(&mut self.allocator, UnsafeCell::new(allocator));
+			
+			let allocator = self.allocator.get_mut();
+			HSynch::hsynch_init(&mut *self.enq, allocator);
+			HSynch::hsynch_init(&mut *self.deq, allocator)
 		}
 	}
 }
 
-impl<T, A: Allocator> Drop for QueueInternal<T, A>
+impl<T, A: Allocator, S: SynchStrategy> Drop for QueueInternal<T, A, S>
 {
 	#[inline(always)]
 	fn drop(&mut self)
 	{
-		self.clear(&|_data|{});
+		self.header.invalidate();
+		self.persist(NonNull::from(&self.header));
+		
+		let mut destructor = self.destructor;
+		let mut allocator = self.allocator.get_mut().clone();
+		self.clear(&mut allocator, &mut destructor);
+		
+		let allocator = self.allocator.get_mut();
+		
+		let head = unsafe { (*self.head.get()).get_non_null() };
+		Node::free_after_drop(head, allocator);
+		
+		if self.record_count != 0
+		{
+			allocator.free_cache_line_size_array(unsafe { self.records.get_non_null() }, self.record_count)
+		}
+		
+		self.enq.free(allocator);
+		self.deq.free(allocator)
 	}
 }
 
-impl<T, A: Allocator> QueueInternal<T, A>
+impl<T, A: Allocator, S: SynchStrategy> QueueInternal<T, A, S>
 {
 	#[inline(always)]
+	#[allow(clippy::mut_from_ref)]
 	fn allocator(&self) -> &mut A
 	{
 		unsafe { &mut *self.allocator.get() }
@@ -53,7 +105,8 @@ impl<T, A: Allocator> QueueInternal<T, A>
 	}
 	
 	#[inline(always)]
-	fn new(mut allocator: A) -> NonNull<Self>
+	#[allow(clippy::too_many_arguments)]
+	fn new(mut allocator: A, destructor: fn(T), capacity: usize, clusters: usize, help_bound: HelpBound, durable: bool, records: usize, shared_between_processes: bool) -> NonNull<Self>
 	{
 		let mut queue = allocator.align_malloc_page_size();
 		
@@ -61,201 +114,494 @@ impl<T, A: Allocator> QueueInternal<T, A>
 		{
 			let queue: &mut Self = queue.as_mut();
 			
-			Synch::ccsynch_init(&queue.enq);
-			Synch::ccsynch_init(&queue.deq);
+			write(&mut queue.header, QueueHeader::new::<T, A, S>());
+			
+			HSynch::hsynch_new(&mut *queue.enq, clusters, help_bound, &mut allocator);
+			HSynch::hsynch_new(&mut *queue.deq, clusters, help_bound, &mut allocator);
 			
 			let dummy = Node::dummy_node(&mut allocator);
 			
-			write(&mut queue.head, UnsafeCell::new(dummy));
-			write(&mut queue.tail, UnsafeCell::new(dummy));
+			write(&mut queue.head, DoubleCacheAligned(UnsafeCell::new(OffsetPointer::null())));
+			queue.head.get_mut().set(dummy.as_ptr());
+			write(&mut queue.tail, DoubleCacheAligned(UnsafeCell::new(OffsetPointer::null())));
+			queue.tail.get_mut().set(dummy.as_ptr());
+			
+			write(&mut queue.allocator, UnsafeCell::new(allocator));
 			
-			write(&mut queue.allocator, UnsafeCell::new(allocator))
+			write(&mut queue.destructor, destructor);
+			
+			write(&mut queue.capacity, capacity);
+			write(&mut queue.enqueued, DoubleCacheAligned(AtomicUsize::new(0)));
+			write(&mut queue.dequeued, DoubleCacheAligned(AtomicUsize::new(0)));
+			write(&mut queue.closed, AtomicBool::new(false));
+			write(&mut queue.durable, durable);
+			
+			write(&mut queue.records, OffsetPointer::null());
+			if records != 0
+			{
+				let array: NonNull<CacheAligned<OperationRecord<T>>> = queue.allocator.get_mut().align_malloc_cache_line_size_array(records);
+				queue.records.set(array.as_ptr());
+			}
+			write(&mut queue.record_count, records);
+			write(&mut queue.shared_between_processes, shared_between_processes);
+			for record in 0 .. records
+			{
+				let record = queue.record(record);
+				OperationRecord::initialize(record.as_ptr());
+				queue.persist(record);
+			}
+			
+			queue.persist(dummy);
 		}
 		
+		let this = unsafe { queue.as_ref() };
+		this.persist(queue);
 		queue
 	}
 	
-	/// Clear the queue.
-	/// Only works on a queue that is acquiescent.
-	/// Similar in some ways to `drop()`.
+	// Frees everything that `allocator_opened()` re-initializes, and drops the allocator, so that the queue can be re-opened from its memory.
+	// Only works on a queue that is acquiescent, and with no per-thread handles.
 	#[inline(always)]
-	fn clear<FreeData: Fn(NonNull<T>)>(&mut self, free_data: &FreeData)
+	fn close_for_reopening(&mut self)
 	{
-		#[inline(always)]
-		fn drop_node<T, A: Allocator, FreeData: Fn(NonNull<T>)>(node: &UnsafeCell<NonNull<Node<T>>>, allocator: &mut A, free_data: &FreeData)
-		{
-			let node = unsafe { *node.get() };
-			Node::clearing_queue_drop(node, allocator, free_data)
-		}
+		let allocator = self.allocator.get_mut();
+		self.enq.free_nodes(allocator);
+		self.deq.free_nodes(allocator);
 		
-		let allocator = self.allocator();
+		unsafe { drop_in_place(self.allocator.get()) }
+	}
+	
+	// The destructor is replaced, as a function pointer is not valid in another process.
+	#[inline(always)]
+	fn reopened(&mut self, allocator: A, destructor: fn(T))
+	{
+		self.allocator_opened(allocator);
+		self.destructor = destructor;
 		
-		// head and tail can be the same (see `new` below)!
-		if self.head.get() != self.tail.get()
+		for record in 0 .. self.record_count
 		{
-			drop_node(&self.head, allocator, free_data);
-			drop_node(&self.tail, allocator, free_data);
-		}
-		else
-		{
-			drop_node(&self.head, allocator, free_data);
+			unsafe { self.record(record).as_ref() }.release()
 		}
 	}
 	
-	// handle is a per-thread object
-	fn enqueue(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, data: NonNull<T>)
+	// Rebuilds what a crash can leave inconsistent, as it is not written back even if durable; the list of nodes from `head` is consistent.
+	// Decides whether the operations recorded as in progress took effect; must be called after `reopened()`, as the nodes of enqueues that did not are freed.
+	#[inline(always)]
+	fn recover(&mut self)
 	{
-		#[inline(always)]
-		fn serial_enqueue<T>(tail: &UnsafeCell<NonNull<Node<T>>>, node: &mut Node<T>)
+		let head = unsafe { (*self.head.get()).get() };
+		let mut last = head;
+		let mut length: usize = 0;
+		loop
 		{
-			let tail = tail.get();
-			
-			unsafe
+			let next = unsafe { (*last).next.get() };
+			if next.is_null()
 			{
-				// (*tail)->next = node
-				write(&mut (*tail).as_mut().next, node);
-				
-				// *tail = node
-				write(tail, NonNull::new_unchecked(node))
+				break
 			}
+			last = next;
+			length += 1;
 		}
 		
-		let node = handle.next;
+		self.tail.get_mut().set(last);
+		self.enqueued.store(length, Relaxed);
+		self.dequeued.store(0, Relaxed);
 		
-		unsafe
+		let (head_index, tail_index) = unsafe { ((*head).index, (*last).index) };
+		for record in 0 .. self.record_count
 		{
-			// Object pooling
-			let mut node = if node.is_not_null()
+			let mut record = self.record(record);
+			if let Some(node) = unsafe { record.as_mut() }.recover(head_index, tail_index)
 			{
-				write(&mut handle.next, null_mut());
-				NonNull::new_unchecked(node)
+				Node::free_after_drop(node, self.allocator.get_mut())
 			}
-			else
-			{
-				handle.allocate_next_node()
-			};
-			
-			let node = node.as_mut();
-			write(&mut node.data, data);
-			write(&mut node.next, null_mut());
-			Self::ccsynch_apply(&self.enq, &mut handle.enq, serial_enqueue, &self.tail, node)
+			self.persist(record)
 		}
 	}
 	
-	// handle is a per-thread object
-	fn dequeue(&self, handle: &mut PerQueueThreadHandleInternal<T, A>) -> Option<NonNull<T>>
+	// Writes back `pointer`, if durable.
+	#[inline(always)]
+	fn persist<P>(&self, pointer: NonNull<P>)
 	{
-		#[inline(always)]
-		fn serial_dequeue<T>(head: &UnsafeCell<NonNull<Node<T>>>, result: &mut Option<NonNull<Node<T>>>)
+		if self.durable
 		{
-			let head = head.get();
+			unsafe { &*self.allocator.get() }.persist(pointer, size_of::<P>())
+		}
+	}
+	
+	#[inline(always)]
+	fn clusters(&self) -> usize
+	{
+		self.enq.cluster_count
+	}
+	
+	// Panics if `record` is not less than `record_count`.
+	#[inline(always)]
+	fn record(&self, record: usize) -> NonNull<OperationRecord<T>>
+	{
+		assert!(record < self.record_count, "record must be less than the number of operation records");
+		unsafe { NonNull::new_unchecked(&mut **self.records.get().add(record)) }
+	}
+	
+	/// Clear the queue.
+	/// Only works on a queue that is acquiescent.
+	/// Similar in some ways to `drop()`, but leaves an empty queue that can continue to be used.
+	/// Frees every node apart from the dummy node at `head` exactly once, iteratively rather than recursively.
+	/// `allocator` is that of the calling process, which may not have created the queue.
+	#[inline(always)]
+	fn clear<FreeData: FnMut(T)>(&mut self, allocator: &mut A, free_data: &mut FreeData)
+	{
+		let head = self.head.get();
+		let tail = self.tail.get();
+		
+		// Detach the nodes after the dummy node first, so that the queue is still valid (and empty) should `free_data` panic.
+		let mut next = unsafe
+		{
+			let mut dummy = (*head).get_non_null();
+			let next = dummy.as_ref().next.get();
+			dummy.as_mut().next.set(null_mut());
 			
-			let mut node = unsafe { *head };
+			// As if every node had been dequeued.
+			dummy.as_mut().index = (*tail).get_non_null().as_ref().index;
+			(*tail).set(dummy.as_ptr());
+			next
+		};
+		
+		self.dequeued.store(self.enqueued.load(Relaxed), Relaxed);
+		
+		while next.is_not_null()
+		{
+			let node = unsafe { NonNull::new_unchecked(next) };
 			
-			let next = unsafe { node.as_ref() }.next;
-			if next.is_not_null()
+			let data = unsafe
 			{
-				let next = unsafe { NonNull::new_unchecked(next) };
-				unsafe
-				{
-					write(&mut node.as_mut().data, next.as_ref().data);
-					*head = next;
-				}
-				
-				*result = Some(node)
-			}
-			else
+				let node = node.as_ref();
+				next = node.next.get();
+				read(node.data.as_ptr())
+			};
+			
+			Node::free_after_drop(node, allocator);
+			
+			free_data(data);
+		}
+	}
+	
+	#[inline(always)]
+	fn is_bounded(&self) -> bool
+	{
+		self.capacity != usize::MAX
+	}
+	
+	// See `serial_enqueue()`: called after a dequeue, an enqueue that does not see what was dequeued, and so does not notify consumers, is seen.
+	#[inline(always)]
+	fn is_empty(&self) -> bool
+	{
+		fence(SeqCst);
+		self.enqueued.load(Acquire) == self.dequeued.load(Acquire)
+	}
+	
+	#[inline(always)]
+	fn is_closed(&self) -> bool
+	{
+		self.closed.load(Acquire)
+	}
+	
+	// Only ever called by the enqueue combiner.
+	// If the queue is bounded, enqueues as much of the chain as there is space for.
+	#[inline(always)]
+	fn serial_enqueue(&self, tail: &UnsafeCell<OffsetPointer<Node<T>>>, request: &mut EnqueueRequest<T>)
+	{
+		if self.closed.load(Relaxed)
+		{
+			request.outcome = EnqueueOutcome::Closed;
+			return
+		}
+		
+		let mut chain = match request.chain.take()
+		{
+			None =>
 			{
-				*result = None
+				self.closed.store(true, Release);
+				self.persist(NonNull::from(&self.closed));
+				request.outcome = EnqueueOutcome::Closed;
+				return
 			}
+			
+			Some(chain) => chain,
+		};
+		
+		let enqueued = self.enqueued.load(Relaxed);
+		
+		// The dequeue combiner may have dequeued more since; if so, the queue is reported full when it no longer is.
+		let space = self.capacity.saturating_sub(enqueued.wrapping_sub(self.dequeued.load(Acquire)));
+		if space == 0
+		{
+			request.chain = Some(chain);
+			request.outcome = EnqueueOutcome::Full;
+			return
+		}
+		
+		if chain.length > space
+		{
+			request.chain = Some(chain.split_off(space));
+			request.outcome = EnqueueOutcome::Full
+		}
+		else
+		{
+			request.outcome = EnqueueOutcome::Enqueued
 		}
 		
-		let mut node: Option<NonNull<Node<T>>> = None;
+		let tail = unsafe { &mut *tail.get() };
+		let mut last = unsafe { tail.get_non_null() };
+		
+		let mut index = unsafe { last.as_ref() }.index;
+		chain.for_each_node(|mut node|
+		{
+			index += 1;
+			unsafe { node.as_mut() }.index = index;
+			self.persist(node)
+		});
+		
+		if let Some(mut record) = request.record
+		{
+			unsafe { record.as_mut() }.enqueuing(index);
+			self.persist(record)
+		}
 		
 		unsafe
 		{
-			Self::ccsynch_apply(&self.deq, &mut handle.deq, serial_dequeue, &self.head, &mut node);
+			// (*tail)->next = chain.first
+			last.as_mut().next.set(chain.first.as_ptr());
+			self.persist(last);
 			
-			match node
+			// *tail = chain.last
+			tail.set(chain.last.as_ptr())
+		}
+		
+		self.enqueued.store(enqueued.wrapping_add(chain.length), Release);
+		
+		// After linking, and paired by its fence with those in `serial_dequeue()` and `is_empty()`, so that either a dequeue that finds the queue empty, or leaves it non-empty, sees the nodes, or this sees what that dequeue dequeued; the nodes may themselves have been dequeued already.
+		fence(SeqCst);
+		request.was_empty = self.dequeued.load(Relaxed).wrapping_sub(enqueued) <= chain.length;
+		request.enqueued = chain.length
+	}
+	
+	// handle is a per-thread object
+	// Returns whether the queue was empty, or the data if the queue is full or closed.
+	fn enqueue(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, data: T) -> Result<bool, EnqueueError<T>>
+	{
+		let node = handle.new_node(data);
+		
+		let record = handle.record;
+		if let Some(mut record) = record
+		{
+			// The node's data is needed should the enqueue not take effect.
+			self.persist(node);
+			unsafe { record.as_mut() }.announce(OperationRecord::<T>::ENQUEUE, node.as_ptr());
+			self.persist(record)
+		}
+		
+		let (_, was_empty, result) = self.enqueue_chain(handle, NodeChain::single(node), record);
+		result.map(|()| was_empty).map_err(|error|
+		{
+			if let Some(mut record) = record
 			{
-				None => None,
-				Some(node) =>
-				{
-					let data = node.as_ref().data;
-					
-					// Object pooling
-					if handle.next.is_not_null()
-					{
-						Node::free_after_drop(node, self.allocator())
-					}
-					else
-					{
-						write(&mut handle.next, node.as_ptr())
-					}
-					
-					Some(data)
-				}
+				unsafe { record.as_mut() }.not_enqueued();
+				self.persist(record)
 			}
-		}
+			
+			let data = unsafe { read(node.as_ref().data.as_ptr()) };
+			handle.recycle_node(node);
+			
+			match error
+			{
+				EnqueueError::Full(_) => EnqueueError::Full(data),
+				EnqueueError::Closed(_) => EnqueueError::Closed(data),
+			}
+		})
 	}
 	
+	// handle is a per-thread object
+	// Returns the number of nodes enqueued, whether the queue was empty before they were, and, if the queue is full or closed, the nodes that were not.
+	// `record` is only for a chain of one node.
 	#[inline(always)]
-	unsafe fn ccsynch_apply<D, Apply: Fn(&UnsafeCell<NonNull<Node<T>>>, &mut D)>(synch: &UnsafeCell<Synch<T>>, synch_handle: &mut SynchHandle<T>, apply: Apply, state: &UnsafeCell<NonNull<Node<T>>>, data: &mut D)
+	fn enqueue_chain(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, chain: NodeChain<T>, record: Option<NonNull<OperationRecord<T>>>) -> (usize, bool, Result<(), EnqueueError<NodeChain<T>>>)
 	{
-		let mut next = synch_handle.next;
+		let request = self.apply_enqueue_request(handle, Some(chain), record);
 		
+		let result = match (request.outcome, request.chain)
+		{
+			(EnqueueOutcome::Enqueued, _) => Ok(()),
+			(EnqueueOutcome::Full, Some(remainder)) => Err(EnqueueError::Full(remainder)),
+			(EnqueueOutcome::Closed, Some(remainder)) => Err(EnqueueError::Closed(remainder)),
+			(_, None) => unreachable!("The combiner returns the remainder of the chain if it is not enqueued"),
+		};
+		(request.enqueued, request.was_empty, result)
+	}
+	
+	// handle is a per-thread object
+	// Closing is serialized with enqueues, so once it has happened nothing more is enqueued; a dequeue that finds the queue empty and closed can rely on it staying empty.
+	fn close(&self, handle: &mut PerQueueThreadHandleInternal<T, A>)
+	{
+		if !self.is_closed()
 		{
-			let next = next.as_mut();
-			write(&mut next.next, AtomicPtr::new(null_mut()));
-			write(&mut next.status, AtomicU32::new(Status::WAIT as u32));
+			self.apply_enqueue_request(handle, None, None);
 		}
+	}
+	
+	#[inline(always)]
+	fn apply_enqueue_request(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, chain: Option<NodeChain<T>>, record: Option<NonNull<OperationRecord<T>>>) -> EnqueueRequest<T>
+	{
+		let request = handle.enqueue_request.as_mut_ptr();
+		unsafe
+		{
+			write
+			(
+				request,
+				EnqueueRequest
+				{
+					chain,
+					enqueued: 0,
+					was_empty: false,
+					outcome: EnqueueOutcome::Closed,
+					record,
+				}
+			);
+			self.enq.hsynch_apply(handle.cluster, &mut handle.enq, &mut handle.allocator, |tail, request| self.serial_enqueue(tail, request), &self.tail, &mut *request);
+			read(request)
+		}
+	}
+	
+	// Only ever called by the dequeue combiner.
+	// Dequeues up to `request.maximum` nodes; the data of the last becomes the new dummy node, so its data is moved into the old dummy node, which is moved to the end of the chain.
+	#[inline(always)]
+	fn serial_dequeue(&self, head: &UnsafeCell<OffsetPointer<Node<T>>>, request: &mut DequeueRequest<T>)
+	{
+		let head = unsafe { &mut *head.get() };
 		
-		let mut current = Synch::swap_tail_returning_previous(synch, next);
-		write(&mut synch_handle.next, current);
+		// Must be loaded before `next`; if the queue was closed, everything ever enqueued is visible.
+		let closed = self.is_closed();
 		
-		let mut status = current.as_ref().acquire_status();
+		let mut dummy = unsafe { head.get_non_null() };
 		
-		if status == Status::WAIT
+		let mut first = unsafe { dummy.as_ref() }.next.get();
+		if first.is_null()
 		{
-			write(&mut current.as_mut().data, transmute_copy(data));
-			current.as_mut().release_next(next);
-			
-			// a do-while loop
-			while
+			// See `serial_enqueue()`: an enqueue that does not see what has been dequeued, and so does not notify consumers, is seen.
+			fence(SeqCst);
+			first = unsafe { dummy.as_ref() }.next.get();
+			if first.is_null()
 			{
-				PAUSE();
-				status = current.as_ref().acquire_status();
-				status == Status::WAIT
+				request.result = Err(if closed { DequeueError::Closed } else { DequeueError::Empty });
+				return
 			}
+		}
+		
+		let first = unsafe { NonNull::new_unchecked(first) };
+		let mut before_last = None;
+		let mut last = first;
+		let mut length = 1;
+		while length < request.maximum
+		{
+			let next = unsafe { last.as_ref() }.next.get();
+			if next.is_null()
 			{
+				break
 			}
+			
+			before_last = Some(last);
+			last = unsafe { NonNull::new_unchecked(next) };
+			length += 1;
 		}
 		
-		if status != Status::DONE
+		unsafe
 		{
-			apply(state, data);
-			
-			let mut current = next;
+			if let Some(mut record) = request.record
+			{
+				record.as_mut().dequeuing(last);
+				self.persist(record)
+			}
 			
-			// next can be null
-			let mut next = current.as_ref().acquire_next();
+			// Moves the data; `last` becomes the new dummy node.
+			copy_nonoverlapping(last.as_ref().data.as_ptr(), dummy.as_mut().data.as_mut_ptr(), 1);
+			head.set(last.as_ptr());
+			self.persist(NonNull::from(&*head));
 			
-			let mut count: usize = 0;
-			const CCSYNCH_HELP_BOUND: usize = 256;
-			while next.is_not_null() && count < CCSYNCH_HELP_BOUND
+			dummy.as_mut().next.set(null_mut());
+		}
+		
+		let first = match before_last
+		{
+			None => dummy,
+			Some(mut before_last) =>
 			{
-				apply(state, transmute_copy(&current.as_ref().data));
-				current.as_mut().release_status_done();
-				
-				current = NonNull::new_unchecked(next);
-				
-				// next can be null
-				next = current.as_ref().acquire_next();
-				
-				count += 1;
+				unsafe { before_last.as_mut().next.set(dummy.as_ptr()) };
+				first
 			}
-			
-			current.as_mut().release_status_ready();
+		};
+		
+		self.dequeued.store(self.dequeued.load(Relaxed).wrapping_add(length), Release);
+		
+		request.result = Ok
+		(
+			NodeChain
+			{
+				first,
+				last: dummy,
+				length,
+			}
+		)
+	}
+	
+	// handle is a per-thread object
+	fn dequeue(&self, handle: &mut PerQueueThreadHandleInternal<T, A>) -> Result<T, DequeueError>
+	{
+		let record = handle.record;
+		if let Some(mut record) = record
+		{
+			unsafe { record.as_mut() }.announce(OperationRecord::<T>::DEQUEUE, null_mut());
+			self.persist(record)
+		}
+		
+		let chain = self.dequeue_chain(handle, 1, record)?;
+		
+		let node = chain.first;
+		let data = unsafe { read(node.as_ref().data.as_ptr()) };
+		handle.recycle_node(node);
+		Ok(data)
+	}
+	
+	// handle is a per-thread object
+	// Returns the number of items dequeued, which is at least one.
+	fn dequeue_batch(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, maximum: usize, into: &mut Vec<T>) -> Result<usize, DequeueError>
+	{
+		let chain = self.dequeue_chain(handle, maximum, None)?;
+		
+		let length = chain.length;
+		into.reserve(length);
+		chain.into_data(handle, into);
+		Ok(length)
+	}
+	
+	// `maximum` must not be zero, and must be one if there is a `record`.
+	#[inline(always)]
+	fn dequeue_chain(&self, handle: &mut PerQueueThreadHandleInternal<T, A>, maximum: usize, record: Option<NonNull<OperationRecord<T>>>) -> Result<NodeChain<T>, DequeueError>
+	{
+		let request = handle.dequeue_request.as_mut_ptr();
+		unsafe
+		{
+			write
+			(
+				request,
+				DequeueRequest
+				{
+					maximum,
+					result: Err(DequeueError::Empty),
+					record,
+				}
+			);
+			self.deq.hsynch_apply(handle.cluster, &mut handle.deq, &mut handle.allocator, |head, request| self.serial_dequeue(head, request), &self.head, &mut *request);
+			read(request).result
 		}
 	}
 }
@@ -0,0 +1,27 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// The last single enqueue or dequeue of a recoverable per-thread handle (see `CcQueue::new_recoverable_per_thread_handle()`), as reported by `CcQueue::recorded_operation()`.
+/// After a crash, whether an operation in progress took effect is decided by `CcQueue::recover()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordedOperation<T>
+{
+	/// No operation has been recorded.
+	None,
+	
+	/// An enqueue took effect.
+	Enqueued,
+	
+	/// An enqueue did not take effect, eg because the queue was full or closed, or because the process exited before it was applied; its data is returned.
+	NotEnqueued(T),
+	
+	/// A dequeue took effect, returning the data.
+	Dequeued(T),
+	
+	/// A dequeue did not take effect, eg because the queue was empty, or because the process exited before it was applied.
+	NotDequeued,
+	
+	/// An enqueue has not yet been decided to have taken effect or not, as a crash interrupted it and the queue was reopened with `CcQueue::from_raw()` rather than `CcQueue::recover()`.
+	InProgress,
+}
@@ -0,0 +1,177 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+#[derive(Debug)]
+#[repr(C)]
+struct StackInternal<T, A: Allocator>
+{
+	synch: DoubleCacheAligned<UnsafeCell<Synch<T>>>,
+	
+	// Null if the stack is empty.
+	top: DoubleCacheAligned<UnsafeCell<OffsetPointer<Node<T>>>>,
+	
+	allocator: UnsafeCell<A>,
+	
+	// Run on each item still in the stack when it is dropped.
+	destructor: fn(T),
+}
+
+impl<T, A: Allocator> AllocatorOpened<A> for StackInternal<T, A>
+{
+	#[inline(always)]
+	fn allocator_opened(&mut self, allocator: A)
+	{
+		unsafe
+		{
+			write(&mut self.allocator, UnsafeCell::new(allocator));
+			
+			Synch::ccsynch_init(&self.synch, self.allocator())
+		}
+	}
+}
+
+impl<T, A: Allocator> Drop for StackInternal<T, A>
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		let mut destructor = self.destructor;
+		self.clear(&mut destructor);
+		
+		let allocator = self.allocator();
+		unsafe { &mut * self.synch.get() }.free_nodes(allocator)
+	}
+}
+
+impl<T, A: Allocator> StackInternal<T, A>
+{
+	#[inline(always)]
+	#[allow(clippy::mut_from_ref)]
+	fn allocator(&self) -> &mut A
+	{
+		unsafe { &mut *self.allocator.get() }
+	}
+	
+	#[inline(always)]
+	fn free_after_drop(this: NonNull<Self>, mut allocator: A)
+	{
+		allocator.free_page_size(this)
+	}
+	
+	#[inline(always)]
+	fn new(mut allocator: A, destructor: fn(T)) -> NonNull<Self>
+	{
+		let mut stack = allocator.align_malloc_page_size();
+		
+		unsafe
+		{
+			let stack: &mut Self = stack.as_mut();
+			
+			Synch::ccsynch_init(&stack.synch, &mut allocator);
+			
+			write(&mut stack.top, DoubleCacheAligned(UnsafeCell::new(OffsetPointer::null())));
+			
+			write(&mut stack.allocator, UnsafeCell::new(allocator));
+			
+			write(&mut stack.destructor, destructor)
+		}
+		
+		stack
+	}
+	
+	/// Clear the stack.
+	/// Only works on a stack that is acquiescent.
+	/// Frees every node exactly once, iteratively rather than recursively.
+	#[inline(always)]
+	fn clear<FreeData: FnMut(T)>(&mut self, free_data: &mut FreeData)
+	{
+		let allocator = self.allocator();
+		
+		// Detach the nodes first, so that the stack is still valid (and empty) should `free_data` panic.
+		let mut next = unsafe
+		{
+			let top = &mut *self.top.get();
+			let next = top.get();
+			top.set(null_mut());
+			next
+		};
+		
+		while next.is_not_null()
+		{
+			let node = unsafe { NonNull::new_unchecked(next) };
+			
+			let data = unsafe
+			{
+				let node = node.as_ref();
+				next = node.next.get();
+				read(node.data.as_ptr())
+			};
+			
+			Node::free_after_drop(node, allocator);
+			
+			free_data(data);
+		}
+	}
+	
+	// Only ever called by the combiner.
+	#[inline(always)]
+	fn serial_push_or_pop(top: &UnsafeCell<OffsetPointer<Node<T>>>, request: &mut StackRequest<T>)
+	{
+		unsafe
+		{
+			let top = &mut *top.get();
+			
+			match *request
+			{
+				StackRequest::Push(mut node) =>
+				{
+					node.as_mut().next.set(top.get());
+					top.set(node.as_ptr())
+				}
+				
+				StackRequest::Pop(ref mut popped) =>
+				{
+					let node = top.get();
+					if node.is_null()
+					{
+						*popped = None
+					}
+					else
+					{
+						top.set((*node).next.get());
+						*popped = Some(NonNull::new_unchecked(node))
+					}
+				}
+			}
+		}
+	}
+	
+	// handle is a per-thread object
+	fn push(&self, handle: &mut PerStackThreadHandleInternal<T, A>, data: T)
+	{
+		let node = handle.new_node(data);
+		
+		let mut request = StackRequest::Push(node);
+		unsafe { Synch::ccsynch_apply(&self.synch, &mut handle.synch, Self::serial_push_or_pop, &self.top, &mut request) };
+	}
+	
+	// handle is a per-thread object
+	fn pop(&self, handle: &mut PerStackThreadHandleInternal<T, A>) -> Option<T>
+	{
+		let mut request = StackRequest::Pop(None);
+		unsafe { Synch::ccsynch_apply(&self.synch, &mut handle.synch, Self::serial_push_or_pop, &self.top, &mut request) };
+		
+		match request
+		{
+			StackRequest::Pop(Some(node)) =>
+			{
+				let data = unsafe { read(node.as_ref().data.as_ptr()) };
+				handle.recycle_node(node);
+				Some(data)
+			}
+			
+			_ => None,
+		}
+	}
+}
@@ -0,0 +1,13 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// Pushes and pops share one combiner, so use the same type of request.
+#[derive(Debug)]
+enum StackRequest<T>
+{
+	Push(NonNull<Node<T>>),
+	
+	// Set by the combiner to the popped node, if any.
+	Pop(Option<NonNull<Node<T>>>),
+}
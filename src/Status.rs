@@ -4,6 +4,7 @@
 
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 #[repr(u32)]
+#[allow(clippy::upper_case_acronyms)]
 enum Status
 {
 	WAIT = 0x0,
@@ -6,28 +6,49 @@
 #[repr(C)]
 struct Synch<T>
 {
-	// Never null
-	tail: AtomicPtr<SynchNode<T>>, // TODO: Make 128-byte aligned
+	// Never null for CC-Synch; null for DSM-Synch when there is no combiner.
+	// A pointer rather than an `AtomicOffsetPointer`, as a `Synch` can be moved (eg in a `CcSynch`); it is re-initialized when its memory is re-opened.
+	tail: DoubleCacheAligned<AtomicPtr<SynchNode<T>>>,
 }
 
-impl<T> Drop for Synch<T>
+impl<T> Synch<T>
 {
 	#[inline(always)]
-	fn drop(&mut self)
+	fn new<A: Allocator>(allocator: &mut A) -> Self
+	{
+		Self
+		{
+			tail: DoubleCacheAligned(AtomicPtr::new(unsafe { SynchNode::ccsynch_init_node(allocator) }.as_ptr())),
+		}
+	}
+	
+	// Must be called, with the allocator the nodes came from, before dropping; the `Synch` is not used again.
+	#[inline(always)]
+	fn free_nodes<A: Allocator>(&mut self, allocator: &mut A)
 	{
+		// For CC-Synch, each SynchNode is owned by either the tail or exactly one `SynchHandle`; `next` is not owning and may point to a node that is still in use.
+		// For DSM-Synch, each SynchNode is owned by exactly one `SynchHandle`, and the tail is null once all requests have been applied.
 		let tail = self.tail.load(Acquire);
-		unsafe { drop_in_place(tail) };
-		SynchNode::free_after_drop(unsafe { NonNull::new_unchecked(tail) })
+		if tail.is_not_null()
+		{
+			SynchNode::free_after_drop(unsafe { NonNull::new_unchecked(tail) }, allocator)
+		}
 	}
-}
-
-impl<T> Synch<T>
-{
+	
+	#[inline(always)]
+	fn dsmsynch_new() -> Self
+	{
+		Self
+		{
+			tail: DoubleCacheAligned(AtomicPtr::new(null_mut())),
+		}
+	}
+	
 	#[inline(always)]
-	unsafe fn ccsynch_init(this: &UnsafeCell<Synch<T>>)
+	unsafe fn ccsynch_init<A: Allocator>(this: &UnsafeCell<Synch<T>>, allocator: &mut A)
 	{
 		let this = { &mut * this.get() };
-		this.tail = AtomicPtr::new(SynchNode::ccsynch_init_node().as_ptr());
+		this.tail = DoubleCacheAligned(AtomicPtr::new(SynchNode::ccsynch_init_node(allocator).as_ptr()));
 	}
 	
 	#[inline(always)]
@@ -37,4 +58,201 @@ impl<T> Synch<T>
 		let raw = this.tail.swap(next.as_ptr(), AcqRel);
 		unsafe { NonNull::new_unchecked(raw) }
 	}
+	
+	// Result can be null
+	#[inline(always)]
+	fn swap_tail_returning_previous_or_null(this: &UnsafeCell<Synch<T>>, next: NonNull<SynchNode<T>>) -> *mut SynchNode<T>
+	{
+		let this = unsafe { &* this.get() };
+		this.tail.swap(next.as_ptr(), AcqRel)
+	}
+	
+	#[inline(always)]
+	fn compare_and_swap_tail_to_null(this: &UnsafeCell<Synch<T>>, current: NonNull<SynchNode<T>>) -> bool
+	{
+		let this = unsafe { &* this.get() };
+		this.tail.compare_exchange(current.as_ptr(), null_mut(), AcqRel, Acquire).is_ok()
+	}
+	
+	// Applies `apply` to `state` and `data` under combining: either this thread becomes the combiner and applies its own and other threads' requests, or another thread applies this thread's request.
+	// All requests to `synch` must use the same `apply`, `state` and type of `data`.
+	#[inline(always)]
+	unsafe fn ccsynch_apply<State, D, Apply: Fn(&State, &mut D)>(synch: &UnsafeCell<Synch<T>>, synch_handle: &mut SynchHandle<T>, apply: Apply, state: &State, data: &mut D)
+	{
+		Self::ccsynch_apply_with_lock(synch, synch_handle, apply, state, data, None, &CombiningHelpBound::new(HelpBound::default()))
+	}
+	
+	// As `ccsynch_apply()`, but the combiner holds `combiner_lock`, if any, whilst it applies requests, which is used by H-Synch, and applies at most `help_bound` requests, including its own.
+	#[inline(always)]
+	unsafe fn ccsynch_apply_with_lock<State, D, Apply: Fn(&State, &mut D)>(synch: &UnsafeCell<Synch<T>>, synch_handle: &mut SynchHandle<T>, apply: Apply, state: &State, data: &mut D, combiner_lock: Option<&CombinerLock>, help_bound: &CombiningHelpBound)
+	{
+		let mut next = synch_handle.next;
+		
+		{
+			let next = next.as_mut();
+			write(&mut next.next, CacheAligned(AtomicOffsetPointer::null()));
+			write(&mut next.status, CacheAligned(AtomicU32::new(Status::WAIT as u32)));
+		}
+		
+		let mut current = Synch::swap_tail_returning_previous(synch, next);
+		write(&mut synch_handle.next, current);
+		
+		let mut status = current.as_ref().acquire_status();
+		
+		if status == Status::WAIT
+		{
+			write(&mut current.as_mut().data, data as *mut D as *mut ());
+			current.as_mut().release_next(next);
+			
+			// a do-while loop
+			while
+			{
+				PAUSE();
+				status = current.as_ref().acquire_status();
+				status == Status::WAIT
+			}
+			{
+			}
+		}
+		
+		if status != Status::DONE
+		{
+			if let Some(combiner_lock) = combiner_lock
+			{
+				combiner_lock.lock()
+			}
+			
+			let (bound, started) = help_bound.combining_started();
+			
+			apply(state, data);
+			
+			let mut current = next;
+			
+			// next can be null
+			let mut next = current.as_ref().acquire_next();
+			
+			// Includes this thread's request.
+			let mut count: usize = 1;
+			while next.is_not_null() && count < bound
+			{
+				apply(state, &mut * (current.as_ref().data as *mut D));
+				current.as_mut().release_status_done();
+				
+				current = NonNull::new_unchecked(next);
+				
+				// next can be null
+				next = current.as_ref().acquire_next();
+				
+				count += 1;
+			}
+			
+			if let Some(combiner_lock) = combiner_lock
+			{
+				combiner_lock.unlock()
+			}
+			
+			current.as_mut().release_status_ready();
+			
+			help_bound.combining_finished(bound, started, count)
+		}
+	}
+	
+	// DSM-Synch: as `ccsynch_apply_with_lock()`, but each thread spins on a node its own handle owns, rather than on its predecessor's, so that it spins on local memory on distributed shared memory (eg NUMA) systems.
+	// Each handle alternates between two nodes; a node is not used again until the request after next, by when no other thread can still refer to it.
+	#[inline(always)]
+	#[allow(clippy::too_many_arguments)]
+	unsafe fn dsmsynch_apply_with_lock<A: Allocator, State, D, Apply: Fn(&State, &mut D)>(synch: &UnsafeCell<Synch<T>>, synch_handle: &mut SynchHandle<T>, allocator: &mut A, apply: Apply, state: &State, data: &mut D, combiner_lock: Option<&CombinerLock>, help_bound: &CombiningHelpBound)
+	{
+		let mut node = synch_handle.dsmsynch_toggle(allocator);
+		
+		{
+			let node = node.as_mut();
+			write(&mut node.next, CacheAligned(AtomicOffsetPointer::null()));
+			write(&mut node.data, data as *mut D as *mut ());
+			write(&mut node.status, CacheAligned(AtomicU32::new(Status::WAIT as u32)));
+		}
+		
+		let predecessor = Synch::swap_tail_returning_previous_or_null(synch, node);
+		
+		if predecessor.is_not_null()
+		{
+			NonNull::new_unchecked(predecessor).as_mut().release_next(node);
+			
+			let mut status;
+			
+			// a do-while loop
+			while
+			{
+				PAUSE();
+				status = node.as_ref().acquire_status();
+				status == Status::WAIT
+			}
+			{
+			}
+			
+			if status == Status::DONE
+			{
+				return
+			}
+		}
+		
+		if let Some(combiner_lock) = combiner_lock
+		{
+			combiner_lock.lock()
+		}
+		
+		let (bound, started) = help_bound.combining_started();
+		
+		// Once a node is released as done, its owner may return and free it, so `next` is read first and the last node is released only after handing over.
+		let mut current = node;
+		let mut count: usize = 0;
+		loop
+		{
+			apply(state, &mut * (current.as_ref().data as *mut D));
+			count += 1;
+			
+			// next can be null
+			let next = current.as_ref().acquire_next();
+			
+			if next.is_null() || count >= bound
+			{
+				break
+			}
+			
+			current.as_mut().release_status_done();
+			current = NonNull::new_unchecked(next);
+		}
+		
+		if let Some(combiner_lock) = combiner_lock
+		{
+			combiner_lock.unlock()
+		}
+		
+		help_bound.combining_finished(bound, started, count);
+		
+		let mut next = current.as_ref().acquire_next();
+		if next.is_null()
+		{
+			if Synch::compare_and_swap_tail_to_null(synch, current)
+			{
+				current.as_mut().release_status_done();
+				return
+			}
+			
+			// A thread has swapped the tail but not yet linked its node.
+			while
+			{
+				PAUSE();
+				next = current.as_ref().acquire_next();
+				next.is_null()
+			}
+			{
+			}
+		}
+		
+		current.as_mut().release_status_done();
+		
+		// The thread that owns `next` becomes the combiner.
+		NonNull::new_unchecked(next).as_mut().release_status_ready();
+	}
 }
@@ -7,24 +7,54 @@
 struct SynchHandle<T>
 {
 	next: NonNull<SynchNode<T>>,
+	
+	// Only used by DSM-Synch, which alternates between `next` and `other`; null until first used.
+	other: *mut SynchNode<T>,
 }
 
-impl<T> Drop for SynchHandle<T>
+impl<T> SynchHandle<T>
 {
 	#[inline(always)]
-	fn drop(&mut self)
+	fn new<A: Allocator>(allocator: &mut A) -> Self
 	{
-		let next = self.next;
-		unsafe { drop_in_place(next.as_ptr()) };
-		SynchNode::free_after_drop(next)
+		Self
+		{
+			next: unsafe { SynchNode::ccsynch_init_node(allocator) },
+			other: null_mut(),
+		}
 	}
-}
-
-impl<T> SynchHandle<T>
-{
+	
+	#[inline(always)]
+	unsafe fn ccsynch_handle_init<A: Allocator>(&mut self, allocator: &mut A)
+	{
+		write(&mut self.next, SynchNode::ccsynch_init_node(allocator));
+		write(&mut self.other, null_mut())
+	}
+	
+	// Must be called, with the allocator the nodes came from, before dropping; the handle is not used again.
+	#[inline(always)]
+	fn free_nodes<A: Allocator>(&mut self, allocator: &mut A)
+	{
+		SynchNode::free_after_drop(self.next, allocator);
+		
+		if self.other.is_not_null()
+		{
+			SynchNode::free_after_drop(unsafe { NonNull::new_unchecked(self.other) }, allocator)
+		}
+	}
+	
+	// Swaps `next` and `other`, returning the node to use for this request.
 	#[inline(always)]
-	unsafe fn ccsynch_handle_init(&mut self)
+	fn dsmsynch_toggle<A: Allocator>(&mut self, allocator: &mut A) -> NonNull<SynchNode<T>>
 	{
-		write(&mut self.next, SynchNode::ccsynch_init_node())
+		if self.other.is_null()
+		{
+			self.other = unsafe { SynchNode::ccsynch_init_node(allocator) }.as_ptr()
+		}
+		
+		let other = self.other;
+		self.other = self.next.as_ptr();
+		self.next = unsafe { NonNull::new_unchecked(other) };
+		self.next
 	}
 }
@@ -6,46 +6,33 @@
 #[repr(C)]
 struct SynchNode<T>
 {
-	next: AtomicPtr<SynchNode<T>>, // TODO: Make 64-byte cache-line aligned
+	// Written by the next thread to make a request, whereas `status` is written by the combiner.
+	next: CacheAligned<AtomicOffsetPointer<SynchNode<T>>>,
 	data: *mut (),
-	status: AtomicU32, // TODO: Make 64-byte cache-line aligned
-}
-
-impl<T> Drop for SynchNode<T>
-{
-	#[inline(always)]
-	fn drop(&mut self)
-	{
-		let next = self.acquire_next();
-		if next.is_not_null()
-		{
-			unsafe { drop_in_place(next) };
-			Self::free_after_drop(unsafe { NonNull::new_unchecked(next) })
-		}
-	}
+	status: CacheAligned<AtomicU32>,
 }
 
 impl<T> SynchNode<T>
 {
 	#[inline(always)]
-	fn free_after_drop(this: NonNull<Self>)
+	fn free_after_drop<A: Allocator>(this: NonNull<Self>, allocator: &mut A)
 	{
-		HeapAllocator.free_cache_line_size(this)
+		allocator.free_cache_line_size(this)
 	}
 	
 	#[inline(always)]
-	unsafe fn ccsynch_init_node() -> NonNull<Self>
+	unsafe fn ccsynch_init_node<A: Allocator>(allocator: &mut A) -> NonNull<Self>
 	{
-		let mut node = HeapAllocator.align_malloc_cache_line_size();
+		let mut node = allocator.align_malloc_cache_line_size();
 		{
 			let node: &mut Self = node.as_mut();
 			
-			write(&mut node.next, AtomicPtr::new(null_mut()));
+			write(&mut node.next, CacheAligned(AtomicOffsetPointer::null()));
 			
 			// Not strictly required as will be overwritten always.
 			write(&mut node.data, null_mut());
 			
-			write(&mut node.status, AtomicU32::new(Status::READY as u32));
+			write(&mut node.status, CacheAligned(AtomicU32::new(Status::READY as u32)));
 		}
 		node
 	}
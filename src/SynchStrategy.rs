@@ -0,0 +1,21 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// The synchronization algorithm a queue uses to combine concurrent requests.
+///
+/// Either `CcSynchStrategy`, the default, or `DsmSynchStrategy`; it can not be implemented outside of this crate.
+#[allow(private_interfaces)]
+pub trait SynchStrategy
+{
+	// Distinguishes strategies in a queue's header (see `QueueHeader`); never changes for a strategy.
+	#[doc(hidden)]
+	const IDENTIFIER: u64;
+	
+	#[doc(hidden)]
+	fn new_synch<T, A: Allocator>(allocator: &mut A) -> Synch<T>;
+	
+	#[doc(hidden)]
+	#[allow(clippy::too_many_arguments)]
+	unsafe fn synch_apply<T, A: Allocator, State, D, Apply: Fn(&State, &mut D)>(synch: &UnsafeCell<Synch<T>>, synch_handle: &mut SynchHandle<T>, allocator: &mut A, apply: Apply, state: &State, data: &mut D, combiner_lock: Option<&CombinerLock>, help_bound: &CombiningHelpBound);
+}
@@ -0,0 +1,73 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+thread_local!
+{
+	static THREAD_LOCAL_PER_QUEUE_THREAD_HANDLES: RefCell<ThreadLocalPerQueueThreadHandles> = const { RefCell::new(ThreadLocalPerQueueThreadHandles(Vec::new())) };
+}
+
+// The per-thread handles this thread has implicitly created, one for each queue it has used.
+// Released when the thread exits.
+struct ThreadLocalPerQueueThreadHandles(Vec<ThreadLocalPerQueueThreadHandle>);
+
+// The `Weak` keeps the allocation of `PerQueueThreadHandleInternals` alive, so its address uniquely identifies a queue even after the queue has been dropped.
+struct ThreadLocalPerQueueThreadHandle
+{
+	per_queue_thread_handle_internals: Weak<dyn ReleasePerQueueThreadHandleInternal>,
+	handle: NonNull<u8>,
+}
+
+impl Drop for ThreadLocalPerQueueThreadHandles
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		for thread_local_per_queue_thread_handle in self.0.drain(..)
+		{
+			if let Some(per_queue_thread_handle_internals) = thread_local_per_queue_thread_handle.per_queue_thread_handle_internals.upgrade()
+			{
+				per_queue_thread_handle_internals.release(thread_local_per_queue_thread_handle.handle)
+			}
+		}
+	}
+}
+
+impl ThreadLocalPerQueueThreadHandles
+{
+	// Returns `None` if thread-local storage has already been destroyed for this thread (eg if called from another thread-local's destructor).
+	#[inline(always)]
+	fn find_or_create<T: 'static, A: 'static + Allocator>(per_queue_thread_handle_internals: &Arc<PerQueueThreadHandleInternals<T, A>>, allocator: &A, clusters: &Clusters) -> Option<NonNull<PerQueueThreadHandleInternal<T, A>>>
+	{
+		THREAD_LOCAL_PER_QUEUE_THREAD_HANDLES.try_with(|this| this.borrow_mut().find_or_create_(per_queue_thread_handle_internals, allocator, clusters)).ok()
+	}
+	
+	#[inline(always)]
+	fn find_or_create_<T: 'static, A: 'static + Allocator>(&mut self, per_queue_thread_handle_internals: &Arc<PerQueueThreadHandleInternals<T, A>>, allocator: &A, clusters: &Clusters) -> NonNull<PerQueueThreadHandleInternal<T, A>>
+	{
+		let key = Arc::as_ptr(per_queue_thread_handle_internals) as *const u8;
+		
+		for thread_local_per_queue_thread_handle in self.0.iter()
+		{
+			if thread_local_per_queue_thread_handle.per_queue_thread_handle_internals.as_ptr() as *const u8 == key
+			{
+				return thread_local_per_queue_thread_handle.handle.cast()
+			}
+		}
+		
+		// Forget handles belonging to queues that have since been dropped.
+		self.0.retain(|thread_local_per_queue_thread_handle| thread_local_per_queue_thread_handle.per_queue_thread_handle_internals.strong_count() != 0);
+		
+		let handle = per_queue_thread_handle_internals.new_per_queue_thread_handle_internal(allocator.clone(), clusters.cluster_of_current_thread());
+		let weak: Weak<PerQueueThreadHandleInternals<T, A>> = Arc::downgrade(per_queue_thread_handle_internals);
+		self.0.push
+		(
+			ThreadLocalPerQueueThreadHandle
+			{
+				per_queue_thread_handle_internals: weak,
+				handle: handle.cast(),
+			}
+		);
+		handle
+	}
+}
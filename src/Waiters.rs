@@ -0,0 +1,306 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// Parks threads, or registers the wakers of asynchronous tasks, until the queue changes state, eg consumers waiting for an empty queue to become non-empty.
+// Waiters only wait after failing an attempt, so a non-zero `waiting` count means the queue was (recently) in the state being waited on; notifiers that find it zero pay for just a fence and a load.
+#[derive(Debug)]
+struct Waiters
+{
+	// Threads that are parked, or about to park, and registered wakers.
+	waiting: AtomicUsize,
+	mutex: Mutex<RegisteredWakers>,
+	condvar: Condvar,
+	
+	// If the queue is shared between processes, parked threads also wake this often, as notifications are not sent to other processes.
+	poll_interval: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct RegisteredWakers
+{
+	// Incremented by every notification; a waiter only parks if there has been no notification since it last tried.
+	epoch: usize,
+	wakers: VecDeque<(usize, Waker)>,
+	next_key: usize,
+}
+
+impl Waiters
+{
+	const POLL_INTERVAL: Duration = Duration::from_millis(1);
+	
+	#[inline(always)]
+	fn new(shared_between_processes: bool) -> Self
+	{
+		Self
+		{
+			waiting: AtomicUsize::new(0),
+			mutex: Mutex::new(RegisteredWakers::default()),
+			condvar: Condvar::new(),
+			poll_interval: if shared_between_processes { Some(Self::POLL_INTERVAL) } else { None },
+		}
+	}
+	
+	// Returns the first successful result of `attempt`, parking in between attempts.
+	#[inline(always)]
+	fn wait_until<R, Attempt: FnMut() -> Option<R>>(&self, attempt: Attempt) -> R
+	{
+		match self.wait_until_deadline(attempt, None)
+		{
+			Some(result) => result,
+			None => unreachable!("Only returns None if there is a deadline"),
+		}
+	}
+	
+	// Returns the first successful result of `attempt`, parking in between attempts, or `None` if `deadline` passes first.
+	// A `deadline` of `None` waits forever.
+	#[inline(always)]
+	fn wait_until_deadline<R, Attempt: FnMut() -> Option<R>>(&self, mut attempt: Attempt, deadline: Option<Instant>) -> Option<R>
+	{
+		loop
+		{
+			let result = attempt();
+			if result.is_some()
+			{
+				return result
+			}
+			
+			let timeout = match deadline
+			{
+				None => None,
+				Some(deadline) =>
+				{
+					let now = Instant::now();
+					if now >= deadline
+					{
+						return None
+					}
+					Some(deadline - now)
+				}
+			};
+			let timeout = match (timeout, self.poll_interval)
+			{
+				(Some(timeout), Some(poll_interval)) => Some(min(timeout, poll_interval)),
+				(timeout, poll_interval) => timeout.or(poll_interval),
+			};
+			
+			// Announce that we are about to wait before trying again, so that a concurrent `notify_one()` either sees us waiting or we see its change.
+			self.waiting.fetch_add(1, SeqCst);
+			let epoch = self.lock().epoch;
+			fence(SeqCst);
+			
+			// `attempt` is not called whilst locked, as a successful attempt may notify other waiters.
+			let result = attempt();
+			if result.is_none()
+			{
+				let guard = self.lock();
+				if guard.epoch == epoch
+				{
+					match timeout
+					{
+						None => drop(self.condvar.wait(guard).unwrap_or_else(PoisonError::into_inner)),
+						Some(timeout) => drop(self.condvar.wait_timeout(guard, timeout).unwrap_or_else(PoisonError::into_inner)),
+					}
+				}
+			}
+			
+			self.waiting.fetch_sub(1, SeqCst);
+			
+			if result.is_some()
+			{
+				return result
+			}
+		}
+	}
+	
+	// Returns `Poll::Ready` with the first successful result of `attempt`, otherwise registers the task's waker under `key` and returns `Poll::Pending`.
+	// The waiter must call `cancel()` with `key` if it is dropped before it is ready.
+	#[inline(always)]
+	fn poll_until<R, Attempt: FnMut() -> Option<R>>(&self, key: &mut Option<usize>, context: &mut Context, mut attempt: Attempt) -> Poll<R>
+	{
+		if let Some(result) = attempt()
+		{
+			self.deregister(key);
+			return Poll::Ready(result)
+		}
+		
+		{
+			let mut guard = self.lock();
+			
+			let registered = match *key
+			{
+				None => None,
+				Some(key) => guard.wakers.iter_mut().find(|&&mut (registered_key, _)| registered_key == key),
+			};
+			
+			match registered
+			{
+				Some(&mut (_, ref mut waker)) => if !waker.will_wake(context.waker())
+				{
+					*waker = context.waker().clone()
+				},
+				
+				// Either never registered, or was registered and has since been notified.
+				None =>
+				{
+					let registered_key = guard.next_key;
+					guard.next_key = registered_key.wrapping_add(1);
+					guard.wakers.push_back((registered_key, context.waker().clone()));
+					*key = Some(registered_key);
+					
+					// As in `wait_until()`, but counted as waiting from registration until `deregister()`, not just for one attempt, as a task is woken rather than parked.
+					self.waiting.fetch_add(1, SeqCst);
+				}
+			}
+		}
+		fence(SeqCst);
+		
+		match attempt()
+		{
+			Some(result) =>
+			{
+				self.deregister(key);
+				Poll::Ready(result)
+			}
+			
+			None => Poll::Pending,
+		}
+	}
+	
+	// Called when a waiter using `poll_until()` is dropped before it is ready.
+	// If the waiter had been notified, the notification is passed on so that it is not lost.
+	#[inline(always)]
+	fn cancel(&self, key: &mut Option<usize>)
+	{
+		if key.is_some() && !self.deregister(key)
+		{
+			self.notify_one()
+		}
+	}
+	
+	// Returns `false` if `key` was registered but has since been notified.
+	#[inline(always)]
+	fn deregister(&self, key: &mut Option<usize>) -> bool
+	{
+		match key.take()
+		{
+			None => true,
+			
+			Some(key) =>
+			{
+				let mut guard = self.lock();
+				match guard.wakers.iter().position(|&(registered_key, _)| registered_key == key)
+				{
+					None => false,
+					Some(index) =>
+					{
+						guard.wakers.remove(index);
+						self.waiting.fetch_sub(1, SeqCst);
+						true
+					}
+				}
+			}
+		}
+	}
+	
+	// Called after changing the queue's state, eg after an enqueue onto an empty queue.
+	// Does nothing if nobody is waiting, which is equivalent to notifying: a waiter counts itself in `waiting` before its last attempt, so one that is not seen here makes that attempt after the change, and sees it.
+	// Registered wakers are preferred to parked threads, as a notified waker is deregistered immediately, whereas a parked thread only stops counting as waiting once it has woken up.
+	#[inline(always)]
+	fn notify_one(&self)
+	{
+		fence(SeqCst);
+		if self.waiting.load(SeqCst) != 0
+		{
+			let waker =
+			{
+				let mut guard = self.lock();
+				guard.epoch = guard.epoch.wrapping_add(1);
+				let waker = guard.wakers.pop_front();
+				if waker.is_some()
+				{
+					self.waiting.fetch_sub(1, SeqCst);
+				}
+				else
+				{
+					self.condvar.notify_one()
+				}
+				waker
+			};
+			
+			if let Some((_, waker)) = waker
+			{
+				waker.wake()
+			}
+		}
+	}
+	
+	// Called after changing the queue's state by `count` items, eg after a batch enqueue.
+	#[inline(always)]
+	fn notify_many(&self, count: usize)
+	{
+		match count
+		{
+			0 => (),
+			1 => self.notify_one(),
+			_ =>
+			{
+				fence(SeqCst);
+				let waiting = self.waiting.load(SeqCst);
+				if waiting != 0
+				{
+					let wakers =
+					{
+						let mut guard = self.lock();
+						guard.epoch = guard.epoch.wrapping_add(1);
+						let notified = min(count, guard.wakers.len());
+						let wakers: Vec<(usize, Waker)> = guard.wakers.drain(.. notified).collect();
+						self.waiting.fetch_sub(wakers.len(), SeqCst);
+						
+						// Notifying more threads than are waiting is pointless.
+						for _ in wakers.len() .. min(count, waiting)
+						{
+							self.condvar.notify_one()
+						}
+						wakers
+					};
+					
+					for (_, waker) in wakers
+					{
+						waker.wake()
+					}
+				}
+			}
+		}
+	}
+	
+	// Called after a change of the queue's state that all waiters must see, eg closing it.
+	#[inline(always)]
+	fn notify_all(&self)
+	{
+		fence(SeqCst);
+		if self.waiting.load(SeqCst) != 0
+		{
+			let wakers =
+			{
+				let mut guard = self.lock();
+				guard.epoch = guard.epoch.wrapping_add(1);
+				let wakers: Vec<(usize, Waker)> = guard.wakers.drain(..).collect();
+				self.waiting.fetch_sub(wakers.len(), SeqCst);
+				self.condvar.notify_all();
+				wakers
+			};
+			
+			for (_, waker) in wakers
+			{
+				waker.wake()
+			}
+		}
+	}
+	
+	#[inline(always)]
+	fn lock(&self) -> MutexGuard<'_, RegisteredWakers>
+	{
+		self.mutex.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+}
@@ -2,6 +2,31 @@
 // Copyright © 2017 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
 
 
+// Evaluates `call` with the constant `length_of_array` being the least power of two not less than `length`, up to 4096, so that an array of `length` can be allocated as a single `[P; length_of_array]`.
+macro_rules! with_default_array_length
+{
+	($length: expr, $length_of_array: ident => $call: expr) =>
+	{
+		match $length.next_power_of_two()
+		{
+			0 ..= 1 => { const $length_of_array: usize = 1; $call }
+			2 => { const $length_of_array: usize = 2; $call }
+			4 => { const $length_of_array: usize = 4; $call }
+			8 => { const $length_of_array: usize = 8; $call }
+			16 => { const $length_of_array: usize = 16; $call }
+			32 => { const $length_of_array: usize = 32; $call }
+			64 => { const $length_of_array: usize = 64; $call }
+			128 => { const $length_of_array: usize = 128; $call }
+			256 => { const $length_of_array: usize = 256; $call }
+			512 => { const $length_of_array: usize = 512; $call }
+			1024 => { const $length_of_array: usize = 1024; $call }
+			2048 => { const $length_of_array: usize = 2048; $call }
+			4096 => { const $length_of_array: usize = 4096; $call }
+			_ => panic!("an allocator without its own array methods can not allocate an array of more than 4096"),
+		}
+	}
+}
+
 /// An allocator allocates and frees memory.
 /// Allocators must implement a simple clone that returns an object that refers to the same memory pool.
 pub trait Allocator: Clone
@@ -10,7 +35,7 @@ pub trait Allocator: Clone
 	const PAGE_SIZE: usize = 4096;
 	
 	/// Cache line size on this architecture.
-	const CACHE_LINE_SIZE: usize = 64;
+	const CACHE_LINE_SIZE: usize = CACHE_LINE_SIZE;
 	
 	/// allocators memory like alloc, but aligned on page size.
 	#[inline(always)]
@@ -26,19 +51,46 @@ pub trait Allocator: Clone
 		self.align_malloc(Self::CACHE_LINE_SIZE)
 	}
 	
-	/// allocators memory like alloc, but aligned.
+	/// allocators memory for `length` contiguous `P`, aligned on cache line.
 	#[inline(always)]
+	fn align_malloc_cache_line_size_array<P>(&mut self, length: usize) -> NonNull<P>
+	{
+		self.align_malloc_array(Self::CACHE_LINE_SIZE, length)
+	}
+	
+	/// allocators memory like alloc, but aligned.
 	fn align_malloc<P>(&mut self, alignment: usize) -> NonNull<P>;
 	
-	/// frees previously allocated memory that was aligned on page size.
+	/// allocators memory for `length` contiguous `P`, aligned.
+	/// By default, uses `align_malloc()` for an array whose length is the least power of two not less than `length`, up to 4096, and panics for more.
 	#[inline(always)]
+	fn align_malloc_array<P>(&mut self, alignment: usize, length: usize) -> NonNull<P>
+	{
+		with_default_array_length!(length, LENGTH_OF_ARRAY => self.align_malloc::<[P; LENGTH_OF_ARRAY]>(alignment).cast())
+	}
+	
+	/// frees previously allocated memory that was aligned on page size.
 	fn free_page_size<P>(&mut self, pointer: NonNull<P>);
 	
 	/// frees previously allocated memory that was aligned on cache line size.
-	#[inline(always)]
 	fn free_cache_line_size<P>(&mut self, pointer: NonNull<P>);
 	
-	/// frees previously allocated memory.
+	/// frees previously allocated memory for `length` contiguous `P` that was aligned on cache line size.
+	/// By default, uses `free_cache_line_size()`, as `align_malloc_array()` uses `align_malloc()`; an allocator overriding one should override the other.
 	#[inline(always)]
+	fn free_cache_line_size_array<P>(&mut self, pointer: NonNull<P>, length: usize)
+	{
+		with_default_array_length!(length, LENGTH_OF_ARRAY => self.free_cache_line_size::<[P; LENGTH_OF_ARRAY]>(pointer.cast()))
+	}
+	
+	/// frees previously allocated memory.
 	fn free<P>(&mut self, pointer: NonNull<P>);
+	
+	/// writes back `length` bytes at `pointer`, and the allocator's own record of what is allocated, to the persistent storage backing the memory, if any, before returning.
+	/// Used by durable queues to order their writes (see `CcQueueConfiguration.durable`).
+	/// Does nothing by default; an allocator of persistent memory might write back the cache lines (eg with `clwb`) then fence.
+	#[inline(always)]
+	fn persist<P>(&self, _pointer: NonNull<P>, _length: usize)
+	{
+	}
 }
@@ -0,0 +1,59 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// Adapts any allocator implementing the unstable `std::alloc::Allocator` trait (the `allocator_api`), such as `std::alloc::Global`.
+/// Only available with the `nightly` feature.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct AllocatorApiAllocator<U: ::std::alloc::Allocator + Clone>(pub U);
+
+impl<U: ::std::alloc::Allocator + Clone> Allocator for AllocatorApiAllocator<U>
+{
+	#[inline(always)]
+	fn align_malloc<P>(&mut self, alignment: usize) -> NonNull<P>
+	{
+		let layout = HeapAllocator::layout::<P>(alignment);
+		
+		match self.0.allocate(layout)
+		{
+			Ok(pointer) => pointer.cast(),
+			Err(_) => handle_alloc_error(layout),
+		}
+	}
+	
+	#[inline(always)]
+	fn align_malloc_array<P>(&mut self, alignment: usize, length: usize) -> NonNull<P>
+	{
+		let layout = HeapAllocator::array_layout::<P>(alignment, length);
+		
+		match self.0.allocate(layout)
+		{
+			Ok(pointer) => pointer.cast(),
+			Err(_) => handle_alloc_error(layout),
+		}
+	}
+	
+	#[inline(always)]
+	fn free_page_size<P>(&mut self, pointer: NonNull<P>)
+	{
+		unsafe { self.0.deallocate(pointer.cast(), HeapAllocator::layout::<P>(Self::PAGE_SIZE)) }
+	}
+	
+	#[inline(always)]
+	fn free_cache_line_size<P>(&mut self, pointer: NonNull<P>)
+	{
+		unsafe { self.0.deallocate(pointer.cast(), HeapAllocator::layout::<P>(Self::CACHE_LINE_SIZE)) }
+	}
+	
+	#[inline(always)]
+	fn free_cache_line_size_array<P>(&mut self, pointer: NonNull<P>, length: usize)
+	{
+		unsafe { self.0.deallocate(pointer.cast(), HeapAllocator::array_layout::<P>(Self::CACHE_LINE_SIZE, length)) }
+	}
+	
+	#[inline(always)]
+	fn free<P>(&mut self, pointer: NonNull<P>)
+	{
+		unsafe { self.0.deallocate(pointer.cast(), Layout::new::<P>()) }
+	}
+}
@@ -9,6 +9,5 @@ pub trait AllocatorOpened<A: Allocator>
 {
 	/// Allocator was opened.
 	/// Reset any temporary state, or adjust pointer offsets.
-	#[inline(always)]
 	fn allocator_opened(&mut self, allocator: A);
 }
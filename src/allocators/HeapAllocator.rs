@@ -11,38 +11,69 @@ impl Allocator for HeapAllocator
 	#[inline(always)]
 	fn align_malloc<P>(&mut self, alignment: usize) -> NonNull<P>
 	{
-		let size = size_of::<P>();
+		let layout = Self::layout::<P>(alignment);
 		
-		let mut heap = Heap;
+		match NonNull::new(unsafe { alloc(layout) })
+		{
+			Some(pointer) => pointer.cast(),
+			None => handle_alloc_error(layout),
+		}
+	}
+	
+	#[inline(always)]
+	fn align_malloc_array<P>(&mut self, alignment: usize, length: usize) -> NonNull<P>
+	{
+		let layout = Self::array_layout::<P>(alignment, length);
 		
-		unsafe { NonNull::new_unchecked(heap.alloc(Layout::from_size_align_unchecked(size, alignment)).unwrap() as *mut P) }
+		match NonNull::new(unsafe { alloc(layout) })
+		{
+			Some(pointer) => pointer.cast(),
+			None => handle_alloc_error(layout),
+		}
 	}
 	
 	#[inline(always)]
 	fn free_page_size<P>(&mut self, pointer: NonNull<P>)
 	{
-		let size = size_of::<P>();
-		
-		let mut heap = Heap;
-		
-		unsafe { heap.dealloc(pointer.as_ptr() as *mut u8, Layout::from_size_align_unchecked(size, Self::PAGE_SIZE)) }
+		unsafe { dealloc(pointer.as_ptr() as *mut u8, Self::layout::<P>(Self::PAGE_SIZE)) }
 	}
 	
 	#[inline(always)]
 	fn free_cache_line_size<P>(&mut self, pointer: NonNull<P>)
 	{
-		let size = size_of::<P>();
-		
-		let mut heap = Heap;
-		
-		unsafe { heap.dealloc(pointer.as_ptr() as *mut u8, Layout::from_size_align_unchecked(size, Self::CACHE_LINE_SIZE)) }
+		unsafe { dealloc(pointer.as_ptr() as *mut u8, Self::layout::<P>(Self::CACHE_LINE_SIZE)) }
+	}
+	
+	#[inline(always)]
+	fn free_cache_line_size_array<P>(&mut self, pointer: NonNull<P>, length: usize)
+	{
+		unsafe { dealloc(pointer.as_ptr() as *mut u8, Self::array_layout::<P>(Self::CACHE_LINE_SIZE, length)) }
 	}
 	
 	#[inline(always)]
 	fn free<P>(&mut self, pointer: NonNull<P>)
 	{
-		let mut heap = Heap;
+		unsafe { dealloc(pointer.as_ptr() as *mut u8, Layout::new::<P>()) }
+	}
+}
+
+impl HeapAllocator
+{
+	// `alignment` is increased if `P` requires more, and the size is never zero, as neither are permitted by `alloc()`.
+	#[inline(always)]
+	fn layout<P>(alignment: usize) -> Layout
+	{
+		let size = max(size_of::<P>(), 1);
+		
+		unsafe { Layout::from_size_align_unchecked(size, max(alignment, align_of::<P>())) }
+	}
+	
+	// As `layout()`, but for `length` contiguous `P`; panics if the size overflows.
+	#[inline(always)]
+	fn array_layout<P>(alignment: usize, length: usize) -> Layout
+	{
+		let size = max(size_of::<P>().checked_mul(length).expect("array size overflows"), 1);
 		
-		unsafe { heap.dealloc(pointer.as_ptr() as *mut u8, Layout::new::<P>()) }
+		Layout::from_size_align(size, max(alignment, align_of::<P>())).expect("array size overflows")
 	}
 }
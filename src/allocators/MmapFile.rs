@@ -0,0 +1,468 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// A file mapped with `MAP_SHARED`, unmapped when dropped.
+// Blocks are allocated from the file's memory after its first page, which holds a `MmapFileHeader`.
+#[derive(Debug)]
+struct MmapFile
+{
+	base: NonNull<u8>,
+	length: usize,
+	file: File,
+	
+	// If not, allocating, freeing and setting the root panic, and the lock is never taken.
+	writable: bool,
+}
+
+unsafe impl Send for MmapFile
+{
+}
+
+unsafe impl Sync for MmapFile
+{
+}
+
+impl Drop for MmapFile
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		unsafe { munmap(self.base.as_ptr() as *mut c_void, self.length) };
+	}
+}
+
+impl MmapFile
+{
+	// Every block's size and offset is a multiple of this.
+	const GRANULE: usize = 64;
+	
+	const DATA_OFFSET: usize = HeapAllocator::PAGE_SIZE;
+	
+	#[inline(always)]
+	fn create(path: &Path, length: usize) -> io::Result<Self>
+	{
+		let length = Self::round_length(length)?;
+		let file = OpenOptions::new().read(true).write(true).create_new(true).open(path)?;
+		Self::create_from_file(file, length)
+	}
+	
+	// `file` must be empty, and `length` rounded.
+	#[inline(always)]
+	fn create_from_file(file: File, length: usize) -> io::Result<Self>
+	{
+		file.set_len(length as u64)?;
+		
+		let this = Self::map(file, length, null_mut(), true)?;
+		unsafe { write(this.header_pointer(), MmapFileHeader::new(this.base.as_ptr() as usize, length, Self::DATA_OFFSET)) };
+		Ok(this)
+	}
+	
+	#[inline(always)]
+	fn round_length(length: usize) -> io::Result<usize>
+	{
+		let page_size = Self::page_size();
+		match length.checked_add(page_size - 1)
+		{
+			Some(length) if length / page_size * page_size > Self::DATA_OFFSET => Ok(length / page_size * page_size),
+			_ => Err(io::Error::new(InvalidInput, "length must be more than a page and not overflow")),
+		}
+	}
+	
+	#[inline(always)]
+	fn open(path: &Path) -> io::Result<Self>
+	{
+		let file = OpenOptions::new().read(true).write(true).open(path)?;
+		
+		// With offset pointers, nothing allocated from the file contains pointers into it, so it can be mapped anywhere.
+		Self::open_file(file, cfg!(feature = "offset-pointers"), true)
+	}
+	
+	#[inline(always)]
+	fn open_read_only(path: &Path) -> io::Result<Self>
+	{
+		let file = OpenOptions::new().read(true).open(path)?;
+		Self::open_file(file, cfg!(feature = "offset-pointers"), false)
+	}
+	
+	// Memory shared between processes at the same time must be mapped at the same address in each, as queues use pointers to it whilst they are being used, even with offset pointers.
+	#[inline(always)]
+	fn open_file(file: File, anywhere: bool, writable: bool) -> io::Result<Self>
+	{
+		let length = file.metadata()?.len();
+		if length <= Self::DATA_OFFSET as u64 || length > usize::MAX as u64
+		{
+			return Err(io::Error::new(InvalidData, "file is not a memory-mapped file allocator's file"))
+		}
+		let length = length as usize;
+		
+		let this = Self::map(file, length, null_mut(), writable)?;
+		let header = this.header();
+		if header.magic != MmapFileHeader::MAGIC || header.length != length as u64
+		{
+			return Err(io::Error::new(InvalidData, "file is not a memory-mapped file allocator's file"))
+		}
+		if header.version != MmapFileHeader::VERSION
+		{
+			return Err(io::Error::new(InvalidData, "memory-mapped file allocator's file has an unsupported version"))
+		}
+		if !header.is_consistent(length)
+		{
+			return Err(io::Error::new(InvalidData, "memory-mapped file allocator's file has an inconsistent header"))
+		}
+		
+		let address = header.address as usize;
+		if anywhere || this.base.as_ptr() as usize == address
+		{
+			return Ok(this)
+		}
+		
+		let file = this.file.try_clone()?;
+		drop(this);
+		let this = Self::map(file, length, address as *mut c_void, writable)?;
+		if this.base.as_ptr() as usize == address
+		{
+			Ok(this)
+		}
+		else
+		{
+			Err(io::Error::new(AddrInUse, "file can not be mapped at the address it was created at"))
+		}
+	}
+	
+	// `address` is only a hint.
+	#[inline(always)]
+	fn map(file: File, length: usize, address: *mut c_void, writable: bool) -> io::Result<Self>
+	{
+		let protection = if writable
+		{
+			PROT_READ | PROT_WRITE
+		}
+		else
+		{
+			PROT_READ
+		};
+		
+		let base = unsafe { mmap(address, length, protection, MAP_SHARED, file.as_raw_fd(), 0) };
+		if base == MAP_FAILED
+		{
+			return Err(io::Error::last_os_error())
+		}
+		
+		Ok
+		(
+			Self
+			{
+				base: unsafe { NonNull::new_unchecked(base as *mut u8) },
+				length,
+				file,
+				writable,
+			}
+		)
+	}
+	
+	#[inline(always)]
+	fn create_shared_memory(name: &str, length: usize) -> io::Result<Self>
+	{
+		let length = Self::round_length(length)?;
+		let file = Self::shm_open(name, O_RDWR | O_CREAT | O_EXCL)?;
+		Self::create_from_file(file, length)
+	}
+	
+	#[inline(always)]
+	fn open_shared_memory(name: &str) -> io::Result<Self>
+	{
+		Self::open_file(Self::shm_open(name, O_RDWR)?, false, true)
+	}
+	
+	#[inline(always)]
+	fn unlink_shared_memory(name: &str) -> io::Result<()>
+	{
+		let name = Self::shared_memory_name(name)?;
+		if unsafe { shm_unlink(name.as_ptr()) } == 0
+		{
+			Ok(())
+		}
+		else
+		{
+			Err(io::Error::last_os_error())
+		}
+	}
+	
+	#[inline(always)]
+	fn shm_open(name: &str, flags: c_int) -> io::Result<File>
+	{
+		let name = Self::shared_memory_name(name)?;
+		match unsafe { shm_open(name.as_ptr(), flags | O_CLOEXEC, 0o600 as mode_t) }
+		{
+			-1 => Err(io::Error::last_os_error()),
+			fd => Ok(unsafe { File::from_raw_fd(fd) }),
+		}
+	}
+	
+	#[inline(always)]
+	fn shared_memory_name(name: &str) -> io::Result<CString>
+	{
+		CString::new(name).map_err(|_| io::Error::new(InvalidInput, "name must not contain a NUL byte"))
+	}
+	
+	#[cfg(any(target_os = "android", target_os = "linux"))]
+	#[inline(always)]
+	fn create_anonymous_shared_memory(length: usize) -> io::Result<Self>
+	{
+		let length = Self::round_length(length)?;
+		let file = match unsafe { memfd_create(b"cc-queue\0".as_ptr() as *const c_char, MFD_CLOEXEC) }
+		{
+			-1 => return Err(io::Error::last_os_error()),
+			fd => unsafe { File::from_raw_fd(fd) },
+		};
+		Self::create_from_file(file, length)
+	}
+	
+	#[inline(always)]
+	fn page_size() -> usize
+	{
+		match unsafe { sysconf(_SC_PAGESIZE) }
+		{
+			page_size if page_size > 0 => page_size as usize,
+			_ => HeapAllocator::PAGE_SIZE,
+		}
+	}
+	
+	#[inline(always)]
+	fn header_pointer(&self) -> *mut MmapFileHeader
+	{
+		self.base.as_ptr() as *mut MmapFileHeader
+	}
+	
+	#[inline(always)]
+	fn header(&self) -> &MmapFileHeader
+	{
+		unsafe { &*self.header_pointer() }
+	}
+	
+	#[inline(always)]
+	fn root<R>(&self) -> Option<NonNull<R>>
+	{
+		match self.header().root.load(Acquire)
+		{
+			0 => None,
+			offset => Some(self.pointer(offset).cast()),
+		}
+	}
+	
+	#[inline(always)]
+	fn set_root<R>(&self, root: Option<NonNull<R>>)
+	{
+		self.assert_writable();
+		
+		let offset = root.map_or(0, |root| self.offset(root.cast()));
+		self.header().root.store(offset, Release);
+		self.msync(self.base, Self::DATA_OFFSET)
+	}
+	
+	// The header is written back too, so that what is allocated is written back before anything that links to it.
+	#[inline(always)]
+	fn persist(&self, pointer: NonNull<u8>, length: usize)
+	{
+		self.msync(pointer, length);
+		self.msync(self.base, Self::DATA_OFFSET)
+	}
+	
+	#[inline(always)]
+	fn msync(&self, pointer: NonNull<u8>, length: usize)
+	{
+		let page_size = Self::page_size();
+		let start = (pointer.as_ptr() as usize) & !(page_size - 1);
+		let end = pointer.as_ptr() as usize + length;
+		let result = unsafe { msync(start as *mut c_void, end - start, MS_SYNC) };
+		assert_eq!(result, 0, "msync() failed: {}", io::Error::last_os_error())
+	}
+	
+	// Panics with `handle_alloc_error()` if the file is full.
+	#[inline(always)]
+	fn allocate(&self, size: usize, alignment: usize) -> NonNull<u8>
+	{
+		self.assert_writable();
+		
+		let alignment = max(alignment, Self::GRANULE);
+		let layout = Layout::from_size_align(size, alignment).expect("invalid size or alignment");
+		let size = match Self::block_size(size)
+		{
+			Some(size) => size,
+			None => handle_alloc_error(layout),
+		};
+		
+		self.lock();
+		let offset = match self.take_free(size, alignment)
+		{
+			Some(offset) => Some(offset),
+			None => self.take_end(size, alignment),
+		};
+		self.unlock();
+		
+		match offset
+		{
+			Some(offset) => self.pointer(offset),
+			None => handle_alloc_error(layout),
+		}
+	}
+	
+	// `size` must be that the block was allocated with.
+	#[inline(always)]
+	fn free(&self, pointer: NonNull<u8>, size: usize)
+	{
+		self.assert_writable();
+		
+		let size = Self::block_size(size).expect("size was allocated, so does not overflow");
+		let offset = self.offset(pointer);
+		
+		self.lock();
+		self.push_free(offset, size);
+		self.unlock()
+	}
+	
+	// Bytes allocated and not yet freed.
+	#[inline(always)]
+	fn allocated(&self) -> usize
+	{
+		let header = self.header();
+		self.lock();
+		let end = unsafe { *header.end.get() } as usize;
+		let mut free = 0;
+		for &first in unsafe { &*header.free_lists.get() }.iter()
+		{
+			let mut offset = first;
+			while offset != 0
+			{
+				let block = self.free_block(offset);
+				free += block.size as usize;
+				offset = block.next;
+			}
+		}
+		self.unlock();
+		
+		end - Self::DATA_OFFSET - free
+	}
+	
+	// Whether `length` bytes at `pointer` are in a block that may have been allocated: after the header, before the first byte never allocated, and starting at a multiple of `GRANULE`.
+	// Freed blocks are not excluded.
+	#[inline(always)]
+	fn contains(&self, pointer: NonNull<u8>, length: usize) -> bool
+	{
+		let offset = (pointer.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize);
+		
+		self.lock();
+		let end = unsafe { *self.header().end.get() } as usize;
+		self.unlock();
+		
+		offset >= Self::DATA_OFFSET && offset & (Self::GRANULE - 1) == 0 && offset <= end && length <= end - offset
+	}
+	
+	// A read-only mapping can not take the lock, so reads what may be changing; it is only used whilst no other process is using the file.
+	#[inline(always)]
+	fn lock(&self)
+	{
+		if self.writable
+		{
+			self.header().lock()
+		}
+	}
+	
+	#[inline(always)]
+	fn unlock(&self)
+	{
+		if self.writable
+		{
+			self.header().unlock()
+		}
+	}
+	
+	#[inline(always)]
+	fn assert_writable(&self)
+	{
+		assert!(self.writable, "memory-mapped file is read-only")
+	}
+	
+	// Must hold the lock.
+	// First fit, as blocks in a list are all the same size unless it is the list of large blocks.
+	#[inline(always)]
+	fn take_free(&self, size: usize, alignment: usize) -> Option<u64>
+	{
+		let mut previous: *mut u64 = unsafe { &mut (*self.header().free_lists.get())[MmapFileHeader::free_list(size)] };
+		loop
+		{
+			let offset = unsafe { *previous };
+			if offset == 0
+			{
+				return None
+			}
+			
+			let block = self.free_block(offset);
+			if block.size as usize == size && offset as usize & (alignment - 1) == 0
+			{
+				unsafe { *previous = block.next };
+				return Some(offset)
+			}
+			previous = &mut block.next;
+		}
+	}
+	
+	// Must hold the lock.
+	// Any space skipped to align the block is freed.
+	#[inline(always)]
+	fn take_end(&self, size: usize, alignment: usize) -> Option<u64>
+	{
+		let end = unsafe { &mut *self.header().end.get() };
+		let unaligned = *end as usize;
+		let offset = unaligned.checked_add(alignment - 1)? & !(alignment - 1);
+		let new_end = offset.checked_add(size)?;
+		if new_end > self.length
+		{
+			return None
+		}
+		
+		*end = new_end as u64;
+		if offset != unaligned
+		{
+			self.push_free(unaligned as u64, offset - unaligned)
+		}
+		Some(offset as u64)
+	}
+	
+	// Must hold the lock.
+	#[inline(always)]
+	fn push_free(&self, offset: u64, size: usize)
+	{
+		let first = unsafe { &mut (*self.header().free_lists.get())[MmapFileHeader::free_list(size)] };
+		unsafe { write(self.pointer(offset).as_ptr() as *mut FreeBlock, FreeBlock { next: *first, size: size as u64 }) };
+		*first = offset
+	}
+	
+	#[inline(always)]
+	#[allow(clippy::mut_from_ref)]
+	fn free_block(&self, offset: u64) -> &mut FreeBlock
+	{
+		unsafe { &mut *(self.pointer(offset).as_ptr() as *mut FreeBlock) }
+	}
+	
+	#[inline(always)]
+	fn block_size(size: usize) -> Option<usize>
+	{
+		Some(max(size, 1).checked_add(Self::GRANULE - 1)? & !(Self::GRANULE - 1))
+	}
+	
+	#[inline(always)]
+	fn pointer(&self, offset: u64) -> NonNull<u8>
+	{
+		unsafe { NonNull::new_unchecked(self.base.as_ptr().add(offset as usize)) }
+	}
+	
+	#[inline(always)]
+	fn offset(&self, pointer: NonNull<u8>) -> u64
+	{
+		let offset = (pointer.as_ptr() as usize).wrapping_sub(self.base.as_ptr() as usize);
+		assert!(offset >= Self::DATA_OFFSET && offset < self.length, "pointer is not in the memory-mapped file");
+		offset as u64
+	}
+}
@@ -0,0 +1,206 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+/// A memory-mapped file allocator allocates and frees memory from a file mapped with `mmap(MAP_SHARED)`, so that what is allocated persists after the process exits.
+/// The free list is kept inside the file; the file's size is fixed when it is created.
+/// Clones refer to the same mapping, which is unmapped when the last clone is dropped.
+///
+/// Anything allocated from the file may contain pointers into it, so the file must be mapped at the same address whenever it is reopened (see `open()`), unless the `offset-pointers` feature is enabled, in which case a queue's links are offsets and the file can be mapped anywhere.
+/// To find a persisted object, such as a queue, after reopening, record it as the file's root (see `set_root()` and `CcQueue::into_raw()`), then call `allocator_opened()` on it (see `CcQueue::from_raw()`).
+///
+/// The file can instead be shared memory (see `create_shared_memory()` and `create_anonymous_shared_memory()`), so that processes can use a queue allocated from it at the same time (see `CcQueue::attach()`).
+/// Forked processes share the parent's mapping; other processes can open the memory by name or from its file descriptor, which is mapped at the same address in each.
+#[derive(Debug, Clone)]
+pub struct MmapFileAllocator(Arc<MmapFile>);
+
+impl Allocator for MmapFileAllocator
+{
+	#[inline(always)]
+	fn align_malloc<P>(&mut self, alignment: usize) -> NonNull<P>
+	{
+		self.0.allocate(size_of::<P>(), max(alignment, align_of::<P>())).cast()
+	}
+	
+	#[inline(always)]
+	fn align_malloc_array<P>(&mut self, alignment: usize, length: usize) -> NonNull<P>
+	{
+		self.0.allocate(Self::array_size::<P>(length), max(alignment, align_of::<P>())).cast()
+	}
+	
+	#[inline(always)]
+	fn free_page_size<P>(&mut self, pointer: NonNull<P>)
+	{
+		self.0.free(pointer.cast(), size_of::<P>())
+	}
+	
+	#[inline(always)]
+	fn free_cache_line_size<P>(&mut self, pointer: NonNull<P>)
+	{
+		self.0.free(pointer.cast(), size_of::<P>())
+	}
+	
+	#[inline(always)]
+	fn free_cache_line_size_array<P>(&mut self, pointer: NonNull<P>, length: usize)
+	{
+		self.0.free(pointer.cast(), Self::array_size::<P>(length))
+	}
+	
+	#[inline(always)]
+	fn free<P>(&mut self, pointer: NonNull<P>)
+	{
+		self.0.free(pointer.cast(), size_of::<P>())
+	}
+	
+	/// Writes back the pages containing `pointer` .. `pointer + length`, and the page holding the free list, using `msync()`.
+	///
+	/// Panics if `msync()` fails.
+	#[inline(always)]
+	fn persist<P>(&self, pointer: NonNull<P>, length: usize)
+	{
+		self.0.persist(pointer.cast(), length)
+	}
+}
+
+impl AsRawFd for MmapFileAllocator
+{
+	#[inline(always)]
+	fn as_raw_fd(&self) -> RawFd
+	{
+		self.0.file.as_raw_fd()
+	}
+}
+
+impl MmapFileAllocator
+{
+	/// Creates a new file at `path`, which must not already exist, of `length` bytes rounded up to a multiple of the page size, and maps it.
+	/// The first page holds the free list; the rest is allocated from.
+	/// Allocating more than the file holds is handled like the heap running out of memory (see `std::alloc::handle_alloc_error()`).
+	#[inline(always)]
+	pub fn create<P: AsRef<Path>>(path: P, length: usize) -> io::Result<Self>
+	{
+		MmapFile::create(path.as_ref(), length).map(|mmap_file| MmapFileAllocator(Arc::new(mmap_file)))
+	}
+	
+	/// Opens and maps a file previously created with `create()`, including by another process.
+	/// What was allocated from it, and not freed, before it was last unmapped is still allocated.
+	///
+	/// Without the `offset-pointers` feature, fails with `std::io::ErrorKind::AddrInUse` if the file can not be mapped at the address it was created at, eg because it is still mapped by this process.
+	#[inline(always)]
+	pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self>
+	{
+		MmapFile::open(path.as_ref()).map(|mmap_file| MmapFileAllocator(Arc::new(mmap_file)))
+	}
+	
+	/// Opens and maps a file previously created with `create()` read-only, eg to inspect a queue in it (see `QueueFile`), whilst no process is using it.
+	/// Allocating, freeing or setting the root panics.
+	///
+	/// Fails as `open()`.
+	#[inline(always)]
+	pub fn open_read_only<P: AsRef<Path>>(path: P) -> io::Result<Self>
+	{
+		MmapFile::open_read_only(path.as_ref()).map(|mmap_file| MmapFileAllocator(Arc::new(mmap_file)))
+	}
+	
+	/// Creates new POSIX shared memory (see `shm_open()`) called `name`, which must not already exist, of `length` bytes rounded up to a multiple of the page size, and maps it.
+	/// `name` should start with a `/` and contain no other.
+	/// The memory persists until unlinked with `unlink_shared_memory()`, or the operating system restarts.
+	#[inline(always)]
+	pub fn create_shared_memory(name: &str, length: usize) -> io::Result<Self>
+	{
+		MmapFile::create_shared_memory(name, length).map(|mmap_file| MmapFileAllocator(Arc::new(mmap_file)))
+	}
+	
+	/// Opens and maps POSIX shared memory previously created with `create_shared_memory()`, eg by another process.
+	///
+	/// Fails with `std::io::ErrorKind::AddrInUse` if the memory can not be mapped at the address it was created at, eg because it is already mapped by this process.
+	#[inline(always)]
+	pub fn open_shared_memory(name: &str) -> io::Result<Self>
+	{
+		MmapFile::open_shared_memory(name).map(|mmap_file| MmapFileAllocator(Arc::new(mmap_file)))
+	}
+	
+	/// Removes the name of POSIX shared memory created with `create_shared_memory()`; the memory is freed once no process has it mapped.
+	#[inline(always)]
+	pub fn unlink_shared_memory(name: &str) -> io::Result<()>
+	{
+		MmapFile::unlink_shared_memory(name)
+	}
+	
+	/// Creates new anonymous shared memory (see `memfd_create()`), of `length` bytes rounded up to a multiple of the page size, and maps it.
+	/// The memory is freed once no process has it mapped or open; share it with a forked process, or pass its file descriptor (see `as_raw_fd()`) to another process, which can then use `from_file()`.
+	/// The file descriptor is closed on `exec()`.
+	#[cfg(any(target_os = "android", target_os = "linux"))]
+	#[inline(always)]
+	pub fn create_anonymous_shared_memory(length: usize) -> io::Result<Self>
+	{
+		MmapFile::create_anonymous_shared_memory(length).map(|mmap_file| MmapFileAllocator(Arc::new(mmap_file)))
+	}
+	
+	/// Maps an open file, or shared memory, previously created with `create()`, `create_shared_memory()` or `create_anonymous_shared_memory()`, eg from a file descriptor received from another process.
+	///
+	/// Fails with `std::io::ErrorKind::AddrInUse` if the file can not be mapped at the address it was created at, eg because it is already mapped by this process.
+	#[inline(always)]
+	pub fn from_file(file: File) -> io::Result<Self>
+	{
+		MmapFile::open_file(file, false, true).map(|mmap_file| MmapFileAllocator(Arc::new(mmap_file)))
+	}
+	
+	/// Opens and maps a file previously created with `create()`, after the last process to use it may have exited abnormally, eg been killed.
+	/// Anything being allocated or freed at the time is leaked.
+	/// No other process may have the file open.
+	#[inline(always)]
+	pub fn recover<P: AsRef<Path>>(path: P) -> io::Result<Self>
+	{
+		MmapFile::open(path.as_ref()).map(|mmap_file|
+		{
+			mmap_file.header().recover();
+			MmapFileAllocator(Arc::new(mmap_file))
+		})
+	}
+	
+	/// The object recorded as the file's root, if any.
+	#[inline(always)]
+	pub fn root<R>(&self) -> Option<NonNull<R>>
+	{
+		self.0.root()
+	}
+	
+	/// Records an object allocated from the file, such as a queue returned by `CcQueue::into_raw()` or `CcQueue::as_raw()`, as the file's root, so that it can be found after the file is reopened; `None` clears it.
+	/// The root is written back to the file as if by `persist()`.
+	///
+	/// Panics if `root` was not allocated from the file.
+	#[inline(always)]
+	pub fn set_root<R>(&self, root: Option<NonNull<R>>)
+	{
+		self.0.set_root(root)
+	}
+	
+	/// Whether `length` bytes at `pointer` lie in a block that may have been allocated from the file: after its first page, before the first byte never allocated, and aligned as every block is.
+	/// The block may since have been freed.
+	#[inline(always)]
+	pub fn contains<P>(&self, pointer: NonNull<P>, length: usize) -> bool
+	{
+		self.0.contains(pointer.cast(), length)
+	}
+	
+	/// The length of the file, in bytes.
+	#[inline(always)]
+	pub fn length(&self) -> usize
+	{
+		self.0.length
+	}
+	
+	/// The number of bytes currently allocated, including any padding of blocks to a multiple of 64 bytes.
+	#[inline(always)]
+	pub fn allocated(&self) -> usize
+	{
+		self.0.allocated()
+	}
+	
+	#[inline(always)]
+	fn array_size<P>(length: usize) -> usize
+	{
+		size_of::<P>().checked_mul(length).expect("array size overflows")
+	}
+}
@@ -0,0 +1,111 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// Occupies the start of the first page of a memory-mapped file; the rest of the file is allocated from.
+// Offsets are from the start of the file; an offset of zero is null.
+#[repr(C)]
+struct MmapFileHeader
+{
+	magic: [u8; 8],
+	
+	// Incremented whenever the layout of this header, or of free blocks, changes.
+	version: u64,
+	
+	// The address the file was mapped at when created; anything allocated from the file may contain pointers that are only valid if it is mapped there again.
+	address: u64,
+	
+	length: u64,
+	
+	root: AtomicU64,
+	
+	// Held whilst `end` or `free_lists` are read or written; a spin lock, so that it works for any process mapping the file.
+	lock: AtomicU32,
+	
+	// The offset of the first byte never allocated.
+	end: UnsafeCell<u64>,
+	
+	// Lists of free blocks, each of a size that is a multiple of `MmapFile::GRANULE`; index `size / GRANULE` for sizes up to `SMALL_MAXIMUM`, and index zero for any larger size.
+	free_lists: UnsafeCell<[u64; MmapFileHeader::FREE_LISTS]>,
+}
+
+impl MmapFileHeader
+{
+	const SMALL_MAXIMUM: usize = 4096;
+	
+	const FREE_LISTS: usize = Self::SMALL_MAXIMUM / MmapFile::GRANULE + 1;
+	
+	const MAGIC: [u8; 8] = *b"CCQMMAP\0";
+	
+	const VERSION: u64 = 1;
+	
+	#[inline(always)]
+	fn new(address: usize, length: usize, end: usize) -> Self
+	{
+		Self
+		{
+			magic: Self::MAGIC,
+			version: Self::VERSION,
+			address: address as u64,
+			length: length as u64,
+			root: AtomicU64::new(0),
+			lock: AtomicU32::new(0),
+			end: UnsafeCell::new(end as u64),
+			free_lists: UnsafeCell::new([0; Self::FREE_LISTS]),
+		}
+	}
+	
+	// The file may be stale, truncated or foreign, so what is written through is checked: `end`, and the first block of each free list, must be in the file and aligned as a block is, and the root must be before `end`.
+	// Must hold the lock, unless nothing else is using the file.
+	#[inline(always)]
+	fn is_consistent(&self, length: usize) -> bool
+	{
+		let end = unsafe { *self.end.get() };
+		if end < MmapFile::DATA_OFFSET as u64 || end > length as u64 || end & (MmapFile::GRANULE as u64 - 1) != 0
+		{
+			return false
+		}
+		
+		let is_allocated = |offset: u64| offset == 0 || (offset >= MmapFile::DATA_OFFSET as u64 && offset < end);
+		let is_block = |offset: u64| is_allocated(offset) && offset & (MmapFile::GRANULE as u64 - 1) == 0;
+		is_allocated(self.root.load(Acquire)) && unsafe { &*self.free_lists.get() }.iter().all(|&first| is_block(first))
+	}
+	
+	#[inline(always)]
+	fn free_list(size: usize) -> usize
+	{
+		if size <= Self::SMALL_MAXIMUM
+		{
+			size / MmapFile::GRANULE
+		}
+		else
+		{
+			0
+		}
+	}
+	
+	#[inline(always)]
+	fn lock(&self)
+	{
+		while self.lock.compare_exchange_weak(0, 1, Acquire, Relaxed).is_err()
+		{
+			while self.lock.load(Relaxed) != 0
+			{
+				spin_loop();
+			}
+		}
+	}
+	
+	#[inline(always)]
+	fn unlock(&self)
+	{
+		self.lock.store(0, Release)
+	}
+	
+	// The lock may have been held by a process that exited abnormally.
+	#[inline(always)]
+	fn recover(&self)
+	{
+		self.unlock()
+	}
+}
@@ -2,13 +2,65 @@
 // Copyright © 2017 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
 
 
-use ::std::heap::Alloc;
-use ::std::heap::Heap;
-use ::std::heap::Layout;
+use super::CACHE_LINE_SIZE;
+#[cfg(unix)] use ::libc::_SC_PAGESIZE;
+#[cfg(any(target_os = "android", target_os = "linux"))] use ::libc::c_char;
+#[cfg(unix)] use ::libc::c_int;
+#[cfg(unix)] use ::libc::c_void;
+#[cfg(unix)] use ::libc::MAP_FAILED;
+#[cfg(unix)] use ::libc::MAP_SHARED;
+#[cfg(any(target_os = "android", target_os = "linux"))] use ::libc::memfd_create;
+#[cfg(any(target_os = "android", target_os = "linux"))] use ::libc::MFD_CLOEXEC;
+#[cfg(unix)] use ::libc::mmap;
+#[cfg(unix)] use ::libc::mode_t;
+#[cfg(unix)] use ::libc::MS_SYNC;
+#[cfg(unix)] use ::libc::msync;
+#[cfg(unix)] use ::libc::munmap;
+#[cfg(unix)] use ::libc::O_CLOEXEC;
+#[cfg(unix)] use ::libc::O_CREAT;
+#[cfg(unix)] use ::libc::O_EXCL;
+#[cfg(unix)] use ::libc::O_RDWR;
+#[cfg(unix)] use ::libc::PROT_READ;
+#[cfg(unix)] use ::libc::PROT_WRITE;
+#[cfg(unix)] use ::libc::shm_open;
+#[cfg(unix)] use ::libc::shm_unlink;
+#[cfg(unix)] use ::libc::sysconf;
+#[cfg(unix)] use ::std::cell::UnsafeCell;
+use ::std::alloc::alloc;
+use ::std::alloc::dealloc;
+use ::std::alloc::handle_alloc_error;
+use ::std::alloc::Layout;
+use ::std::cmp::max;
+#[cfg(unix)] use ::std::ffi::CString;
+#[cfg(unix)] use ::std::fs::File;
+#[cfg(unix)] use ::std::fs::OpenOptions;
+#[cfg(unix)] use ::std::hint::spin_loop;
+#[cfg(unix)] use ::std::io;
+#[cfg(unix)] use ::std::io::ErrorKind::AddrInUse;
+#[cfg(unix)] use ::std::io::ErrorKind::InvalidData;
+#[cfg(unix)] use ::std::io::ErrorKind::InvalidInput;
+use ::std::mem::align_of;
 use ::std::mem::size_of;
 use ::std::ptr::NonNull;
+#[cfg(unix)] use ::std::os::unix::io::AsRawFd;
+#[cfg(unix)] use ::std::os::unix::io::FromRawFd;
+#[cfg(unix)] use ::std::os::unix::io::RawFd;
+#[cfg(unix)] use ::std::path::Path;
+#[cfg(unix)] use ::std::ptr::null_mut;
+#[cfg(unix)] use ::std::ptr::write;
+#[cfg(unix)] use ::std::sync::Arc;
+#[cfg(unix)] use ::std::sync::atomic::AtomicU32;
+#[cfg(unix)] use ::std::sync::atomic::AtomicU64;
+#[cfg(unix)] use ::std::sync::atomic::Ordering::Acquire;
+#[cfg(unix)] use ::std::sync::atomic::Ordering::Relaxed;
+#[cfg(unix)] use ::std::sync::atomic::Ordering::Release;
 
 
 include!("Allocator.rs");
 include!("AllocatorOpened.rs");
+#[cfg(unix)] include!("FreeBlock.rs");
 include!("HeapAllocator.rs");
+#[cfg(unix)] include!("MmapFile.rs");
+#[cfg(unix)] include!("MmapFileAllocator.rs");
+#[cfg(unix)] include!("MmapFileHeader.rs");
+#[cfg(feature = "nightly")] include!("AllocatorApiAllocator.rs");
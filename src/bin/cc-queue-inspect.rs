@@ -0,0 +1,196 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+//! # cc-queue-inspect
+//! Inspects a queue in a file allocated from by a `MmapFileAllocator`, recorded as the file's root, whilst no process is using it.
+//!
+//! Usage: `cc-queue-inspect [--json] [--repair-tail] FILE`
+//!
+//! Reports the number of items in the queue, checks that the chain of its nodes from `head` has no dangling links or cycles and ends at `tail`, and dumps the bytes of each item, as hex or, with `--json`, as JSON.
+//! The file is opened read-only unless `--repair-tail` is given, which makes `tail` the last node in the chain if the chain is whole.
+//!
+//! Exits with 0 if the queue is consistent, 1 if it is not, and 2 if the file can not be inspected.
+
+
+extern crate cc_queue;
+
+
+#[cfg(unix)] use ::cc_queue::BrokenLink;
+#[cfg(unix)] use ::cc_queue::QueueFile;
+#[cfg(unix)] use ::cc_queue::QueueInspection;
+use ::std::env::args_os;
+use ::std::ffi::OsString;
+#[cfg(unix)] use ::std::fmt::Write;
+#[cfg(unix)] use ::std::io;
+use ::std::path::PathBuf;
+use ::std::process::ExitCode;
+
+
+const USAGE: &str = "usage: cc-queue-inspect [--json] [--repair-tail] FILE";
+
+
+struct Options
+{
+	json: bool,
+	repair_tail: bool,
+	path: PathBuf,
+}
+
+impl Options
+{
+	fn parse(arguments: impl Iterator<Item = OsString>) -> Result<Self, String>
+	{
+		let mut json = false;
+		let mut repair_tail = false;
+		let mut path = None;
+		for argument in arguments
+		{
+			match argument.to_str()
+			{
+				Some("--json") => json = true,
+				Some("--repair-tail") => repair_tail = true,
+				Some("--help") | Some("-h") => return Err(USAGE.to_string()),
+				Some(option) if option.starts_with('-') => return Err(format!("unknown option {}\n{}", option, USAGE)),
+				_ if path.is_none() => path = Some(PathBuf::from(argument)),
+				_ => return Err(USAGE.to_string()),
+			}
+		}
+		
+		match path
+		{
+			Some(path) => Ok(Options { json, repair_tail, path }),
+			None => Err(USAGE.to_string()),
+		}
+	}
+}
+
+fn main() -> ExitCode
+{
+	let options = match Options::parse(args_os().skip(1))
+	{
+		Ok(options) => options,
+		Err(message) =>
+		{
+			eprintln!("{}", message);
+			return ExitCode::from(2)
+		}
+	};
+	
+	match inspect(&options)
+	{
+		Ok(true) => ExitCode::SUCCESS,
+		Ok(false) => ExitCode::from(1),
+		Err(error) =>
+		{
+			eprintln!("cc-queue-inspect: {}: {}", options.path.display(), error);
+			ExitCode::from(2)
+		}
+	}
+}
+
+// Returns whether the queue is consistent.
+#[cfg(unix)]
+fn inspect(options: &Options) -> io::Result<bool>
+{
+	let mut file = if options.repair_tail
+	{
+		QueueFile::open_for_repair(&options.path)?
+	}
+	else
+	{
+		QueueFile::open(&options.path)?
+	};
+	
+	// A broken chain is reported rather than repaired.
+	let repaired = options.repair_tail && file.inspect().broken_link.is_none() && file.repair_tail()?;
+	
+	let inspection = file.inspect();
+	let report = if options.json
+	{
+		json(&file, &inspection, options.repair_tail.then_some(repaired))
+	}
+	else
+	{
+		text(&file, &inspection, options.repair_tail.then_some(repaired))
+	};
+	print!("{}", report);
+	
+	Ok(inspection.is_consistent())
+}
+
+#[cfg(not(unix))]
+fn inspect(_options: &Options) -> Result<bool, &'static str>
+{
+	Err("memory-mapped files are only supported on Unix")
+}
+
+#[cfg(unix)]
+fn text(file: &QueueFile, inspection: &QueueInspection, repaired: Option<bool>) -> String
+{
+	let mut report = String::new();
+	writeln!(report, "version: {}", file.version()).unwrap();
+	writeln!(report, "data: {} bytes, aligned to {}", file.data_size(), file.data_alignment()).unwrap();
+	writeln!(report, "items: {}", inspection.items.len()).unwrap();
+	writeln!(report, "recorded length: {}", inspection.recorded_length).unwrap();
+	match inspection.broken_link
+	{
+		None => writeln!(report, "chain: whole").unwrap(),
+		Some(broken_link) => writeln!(report, "chain: {}", broken_link).unwrap(),
+	}
+	writeln!(report, "tail: {}", if inspection.tail_is_last { "last node" } else { "not the last node" }).unwrap();
+	if let Some(repaired) = repaired
+	{
+		writeln!(report, "repaired: {}", if repaired { "yes" } else { "no" }).unwrap();
+	}
+	
+	for (index, item) in inspection.items.iter().enumerate()
+	{
+		write!(report, "{}: ", index).unwrap();
+		for byte in item.iter()
+		{
+			write!(report, "{:02x}", byte).unwrap();
+		}
+		report.push('\n');
+	}
+	report
+}
+
+#[cfg(unix)]
+fn json(file: &QueueFile, inspection: &QueueInspection, repaired: Option<bool>) -> String
+{
+	let mut report = String::new();
+	write!(report, "{{\"version\":{},\"data_size\":{},\"data_alignment\":{},\"length\":{},\"recorded_length\":{}", file.version(), file.data_size(), file.data_alignment(), inspection.items.len(), inspection.recorded_length).unwrap();
+	match inspection.broken_link
+	{
+		None => report.push_str(",\"broken_link\":null"),
+		Some(BrokenLink::Dangling(after)) => write!(report, ",\"broken_link\":{{\"kind\":\"dangling\",\"after\":{}}}", after).unwrap(),
+		Some(BrokenLink::Cycle(after)) => write!(report, ",\"broken_link\":{{\"kind\":\"cycle\",\"after\":{}}}", after).unwrap(),
+	}
+	write!(report, ",\"tail_is_last\":{}", inspection.tail_is_last).unwrap();
+	if let Some(repaired) = repaired
+	{
+		write!(report, ",\"repaired\":{}", repaired).unwrap();
+	}
+	
+	report.push_str(",\"items\":[");
+	for (index, item) in inspection.items.iter().enumerate()
+	{
+		if index != 0
+		{
+			report.push(',');
+		}
+		report.push('[');
+		for (index, byte) in item.iter().enumerate()
+		{
+			if index != 0
+			{
+				report.push(',');
+			}
+			write!(report, "{}", byte).unwrap();
+		}
+		report.push(']');
+	}
+	report.push_str("]}\n");
+	report
+}
@@ -2,8 +2,7 @@
 // Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
 
 
-#![feature(allocator_api)]
-#![feature(integer_atomics)]
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
 #![deny(missing_docs)]
 
 
@@ -12,35 +11,106 @@
 //! * Non-blocking
 //! * Thread-safe
 //! * Concurrent
-//! * Unbounded
+//! * Unbounded, or optionally bounded
 //! * Faster than the MSQueue (Michael-Scott Queue, as used in Java)
 //!
 //! And suitable for use with multiple memory allocators, including ones that use persistent memory.
+//! On Unix, `allocators::MmapFileAllocator` allocates from a memory-mapped file, so that a queue can survive the process exiting (see `CcQueue::into_raw()` and `CcQueue::from_raw()`).
+//! A header at the start of a queue's memory records its format version and layout, so that memory which is not a queue of the same type, built the same way, is refused rather than misread.
+//! A queue in a file can be inspected, and a stale tail repaired, without knowing its type, using `QueueFile` or the `cc-queue-inspect` binary.
+//! The `offset-pointers` feature stores a queue's links as offsets rather than pointers, so that such a file can be reopened at any address.
+//! A durable queue (see `CcQueueConfiguration.durable`) writes back its links in an order that a crash can not leave torn, and can be recovered using `CcQueue::recover()`.
+//! A recoverable per-thread handle (see `CcQueue::new_recoverable_per_thread_handle()`) records its last enqueue or dequeue, so that after a crash it can be determined whether it took effect, and what it returned.
+//! Allocated from shared memory, a queue can be used by several processes at once (see `CcQueueConfiguration.shared_between_processes` and `CcQueue::attach()`).
+//!
+//! A CC Stack, `CcStack`, built on the same machinery, is also provided for when LIFO order is needed.
+//!
+//! The CC-Synch combining machinery it is built on is also available, as `CcSynch`, to make any sequential object concurrent.
+//!
+//! A queue combines concurrent requests using CC-Synch by default; DSM-Synch, which is better suited to distributed shared memory and NUMA systems, can be used instead (see `CcQueue::with_synch_strategy()` and `DsmSynchStrategy`).
+//!
+//! Builds on stable Rust.
+//! The `nightly` feature enables additional functionality that requires a nightly compiler.
 //!
 //! ## To use it
 //! 1. Create a new instance of `CCQueue`.
-//! 2. Create a handle per-thread using `CCQueue.new_per_thread_handle()`.
-//! 3. Enqueue and dequeue
+//! 2. Enqueue and dequeue values using `CCQueue.enqueue()` and `CCQueue.dequeue()`; the queue takes ownership of enqueued values and gives it back on dequeue.
+//! 3. Optionally, call `CCQueue.close()` once nothing more will be enqueued; consumers drain what remains and are then told the queue is closed.
 //!
 //! ## Notes on the API
-//! The API may need to change to make it easier to manage the per-thread handle objects.
+//! Each thread needs a per-thread handle to access the queue.
+//! `CCQueue.enqueue()` and `CCQueue.dequeue()` create one on first use and store it in thread-local storage; it is freed when the thread exits or the queue is dropped.
+//! Alternatively, create and manage a handle explicitly using `CCQueue.new_per_thread_handle()`, which avoids the thread-local lookup.
 //!
 
 
+#[cfg(feature = "futures")] extern crate futures_core;
+#[cfg(unix)] extern crate libc;
+
+
 use self::allocators::*;
+#[cfg(unix)] use ::libc::pthread_atfork;
+use ::std::cell::RefCell;
 use ::std::cell::UnsafeCell;
+#[cfg(unix)] use ::std::cmp::max;
+use ::std::cmp::min;
+#[cfg(unix)] use ::std::collections::HashSet;
+use ::std::collections::VecDeque;
+use ::std::error::Error;
+use ::std::fmt;
+use ::std::fmt::Debug;
+use ::std::fmt::Display;
+use ::std::fmt::Formatter;
+use ::std::fs::read_dir;
+use ::std::fs::read_to_string;
+use ::std::future::Future;
+use ::std::hint::spin_loop as PAUSE;
+#[cfg(unix)] use ::std::io;
+#[cfg(unix)] use ::std::io::ErrorKind::InvalidData;
+#[cfg(unix)] use ::std::io::ErrorKind::PermissionDenied;
+use ::std::marker::PhantomData;
+use ::std::mem::ManuallyDrop;
+use ::std::mem::MaybeUninit;
+use ::std::mem::align_of;
+use ::std::mem::offset_of;
+use ::std::mem::size_of;
 use ::std::mem::transmute;
-use ::std::mem::transmute_copy;
+use ::std::ops::Deref;
+use ::std::ops::DerefMut;
+use ::std::path::Path;
+use ::std::path::PathBuf;
+use ::std::pin::Pin;
+use ::std::ptr::copy_nonoverlapping;
 use ::std::ptr::drop_in_place;
 use ::std::ptr::NonNull;
 use ::std::ptr::null_mut;
+use ::std::ptr::read;
 use ::std::ptr::write;
+#[cfg(unix)] use ::std::slice::from_raw_parts;
+use ::std::sync::Arc;
+use ::std::sync::Condvar;
+use ::std::sync::Mutex;
+use ::std::sync::MutexGuard;
+use ::std::sync::Once;
+use ::std::sync::PoisonError;
+use ::std::sync::Weak;
+use ::std::sync::atomic::AtomicBool;
+#[cfg(feature = "offset-pointers")] use ::std::sync::atomic::AtomicIsize;
 use ::std::sync::atomic::AtomicPtr;
 use ::std::sync::atomic::AtomicU32;
+use ::std::sync::atomic::AtomicUsize;
+use ::std::sync::atomic::fence;
+use ::std::sync::atomic::Ordering;
 use ::std::sync::atomic::Ordering::AcqRel;
 use ::std::sync::atomic::Ordering::Acquire;
+use ::std::sync::atomic::Ordering::Relaxed;
 use ::std::sync::atomic::Ordering::Release;
-use ::std::sync::atomic::spin_loop_hint as PAUSE;
+use ::std::sync::atomic::Ordering::SeqCst;
+use ::std::task::Context;
+use ::std::task::Poll;
+use ::std::task::Waker;
+use ::std::time::Duration;
+use ::std::time::Instant;
 
 
 /// Allocators allow customization of the backing memory used by this queue.
@@ -48,13 +118,53 @@ use ::std::sync::atomic::spin_loop_hint as PAUSE;
 pub mod allocators;
 
 
+include!("AtomicOffsetPointer.rs");
+#[cfg(unix)] include!("BrokenLink.rs");
+include!("CacheAligned.rs");
 include!("CcQueue.rs");
+include!("CcQueueConfiguration.rs");
+include!("CcStack.rs");
+include!("CcSynch.rs");
+include!("CcSynchHandle.rs");
+include!("CcSynchStrategy.rs");
+include!("Clustering.rs");
+include!("Clusters.rs");
+include!("CombinerLock.rs");
+include!("CombiningHelpBound.rs");
+include!("DequeueError.rs");
+include!("DequeueFuture.rs");
+include!("DequeueRequest.rs");
+include!("DequeueStream.rs");
+include!("DoubleCacheAligned.rs");
+include!("DsmSynchStrategy.rs");
+include!("EnqueueError.rs");
+include!("EnqueueOutcome.rs");
+include!("EnqueueRequest.rs");
+include!("Forks.rs");
+include!("HSynch.rs");
+include!("HelpBound.rs");
 include!("IsNotNull.rs");
 include!("Node.rs");
+include!("NodeChain.rs");
+include!("OffsetPointer.rs");
+include!("OperationRecord.rs");
 include!("PerQueueThreadHandle.rs");
 include!("PerQueueThreadHandleInternal.rs");
+include!("PerQueueThreadHandleInternals.rs");
+include!("PerStackThreadHandle.rs");
+include!("PerStackThreadHandleInternal.rs");
+#[cfg(unix)] include!("QueueFile.rs");
+include!("QueueHeader.rs");
+include!("QueueHeaderError.rs");
 include!("QueueInternal.rs");
+#[cfg(unix)] include!("QueueInspection.rs");
+include!("RecordedOperation.rs");
+include!("StackInternal.rs");
+include!("StackRequest.rs");
 include!("Status.rs");
 include!("Synch.rs");
 include!("SynchHandle.rs");
 include!("SynchNode.rs");
+include!("SynchStrategy.rs");
+include!("ThreadLocalPerQueueThreadHandles.rs");
+include!("Waiters.rs");
@@ -0,0 +1,225 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::CcStack;
+use ::cc_queue::Clustering;
+use ::cc_queue::DsmSynchStrategy;
+use ::cc_queue::allocators::Allocator;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::ptr::NonNull;
+use ::std::sync::Arc;
+use ::std::sync::atomic::AtomicUsize;
+use ::std::sync::atomic::Ordering::SeqCst;
+use ::std::thread;
+
+
+// Counts the allocations it has made that have not yet been freed.
+#[derive(Debug, Default, Clone)]
+struct CountingAllocator(Arc<AtomicUsize>);
+
+impl Allocator for CountingAllocator
+{
+	fn align_malloc<P>(&mut self, alignment: usize) -> NonNull<P>
+	{
+		self.0.fetch_add(1, SeqCst);
+		HeapAllocator.align_malloc(alignment)
+	}
+	
+	fn align_malloc_array<P>(&mut self, alignment: usize, length: usize) -> NonNull<P>
+	{
+		self.0.fetch_add(1, SeqCst);
+		HeapAllocator.align_malloc_array(alignment, length)
+	}
+	
+	fn free_page_size<P>(&mut self, pointer: NonNull<P>)
+	{
+		self.0.fetch_sub(1, SeqCst);
+		HeapAllocator.free_page_size(pointer)
+	}
+	
+	fn free_cache_line_size<P>(&mut self, pointer: NonNull<P>)
+	{
+		self.0.fetch_sub(1, SeqCst);
+		HeapAllocator.free_cache_line_size(pointer)
+	}
+	
+	fn free_cache_line_size_array<P>(&mut self, pointer: NonNull<P>, length: usize)
+	{
+		self.0.fetch_sub(1, SeqCst);
+		HeapAllocator.free_cache_line_size_array(pointer, length)
+	}
+	
+	fn free<P>(&mut self, pointer: NonNull<P>)
+	{
+		self.0.fetch_sub(1, SeqCst);
+		HeapAllocator.free(pointer)
+	}
+}
+
+impl CountingAllocator
+{
+	fn outstanding(&self) -> usize
+	{
+		self.0.load(SeqCst)
+	}
+}
+
+// As `CountingAllocator`, but relying on the default array methods of `Allocator`.
+#[derive(Debug, Default, Clone)]
+struct CountingAllocatorWithoutArrays(CountingAllocator);
+
+impl Allocator for CountingAllocatorWithoutArrays
+{
+	fn align_malloc<P>(&mut self, alignment: usize) -> NonNull<P>
+	{
+		self.0.align_malloc(alignment)
+	}
+	
+	fn free_page_size<P>(&mut self, pointer: NonNull<P>)
+	{
+		self.0.free_page_size(pointer)
+	}
+	
+	fn free_cache_line_size<P>(&mut self, pointer: NonNull<P>)
+	{
+		self.0.free_cache_line_size(pointer)
+	}
+	
+	fn free<P>(&mut self, pointer: NonNull<P>)
+	{
+		self.0.free(pointer)
+	}
+}
+
+#[test]
+fn per_thread_handles_are_allocated_from_queue_allocator()
+{
+	let allocator = CountingAllocator::default();
+	let queue = CcQueue::new(allocator.clone());
+	let without_handles = allocator.outstanding();
+	assert_ne!(without_handles, 0);
+	
+	{
+		let mut handle = queue.new_per_thread_handle();
+		assert!(allocator.outstanding() > without_handles);
+		
+		handle.enqueue(1).unwrap();
+		assert_eq!(handle.dequeue(), Ok(1));
+	}
+	assert_eq!(allocator.outstanding(), without_handles);
+	
+	drop(queue);
+	assert_eq!(allocator.outstanding(), 0);
+}
+
+#[test]
+fn combining_records_of_every_cluster_are_allocated_from_queue_allocator()
+{
+	fn cluster_of_current_thread() -> usize
+	{
+		0
+	}
+	
+	let one_cluster = CountingAllocator::default();
+	drop(CcQueue::<usize, _>::new(one_cluster.clone()));
+	
+	let allocator = CountingAllocator::default();
+	let queue = CcQueue::<usize, _>::configured(allocator.clone(), CcQueueConfiguration { clustering: Clustering::Explicit { clusters: 4, cluster_of_current_thread }, ..CcQueueConfiguration::default() });
+	let queue_with_one_cluster = CcQueue::<usize, _>::new(one_cluster.clone());
+	
+	// Each further cluster has an initial combining node for each of enqueue and dequeue.
+	assert_eq!(allocator.outstanding(), one_cluster.outstanding() + 3 * 2);
+	
+	drop(queue);
+	drop(queue_with_one_cluster);
+	assert_eq!(allocator.outstanding(), 0);
+	assert_eq!(one_cluster.outstanding(), 0);
+}
+
+#[test]
+fn everything_is_freed_to_queue_allocator_after_concurrent_use()
+{
+	// Threads are assigned clusters round-robin.
+	fn cluster_of_current_thread() -> usize
+	{
+		static NEXT_CLUSTER: AtomicUsize = AtomicUsize::new(0);
+		
+		thread_local!
+		{
+			static CLUSTER: usize = NEXT_CLUSTER.fetch_add(1, SeqCst);
+		}
+		
+		CLUSTER.with(|cluster| *cluster)
+	}
+	
+	let allocator = CountingAllocator::default();
+	
+	{
+		let queue = Arc::new(CcQueue::with_synch_strategy(allocator.clone(), CcQueueConfiguration { clustering: Clustering::Explicit { clusters: 2, cluster_of_current_thread }, ..CcQueueConfiguration::default() }, DsmSynchStrategy));
+		
+		let threads: Vec<_> = (0 .. 4).map(|thread|
+		{
+			let queue = queue.clone();
+			thread::spawn(move ||
+			{
+				for item in 0 .. 1_000
+				{
+					queue.enqueue(thread * 1_000 + item).unwrap();
+					queue.dequeue().unwrap();
+				}
+			})
+		}).collect();
+		
+		for thread in threads
+		{
+			thread.join().unwrap();
+		}
+		
+		queue.enqueue_batch(0 .. 100).unwrap();
+	}
+	
+	assert_eq!(allocator.outstanding(), 0);
+}
+
+#[test]
+fn stack_allocates_everything_from_its_allocator()
+{
+	let allocator = CountingAllocator::default();
+	
+	{
+		let stack = CcStack::new(allocator.clone());
+		let mut handle = stack.new_per_thread_handle();
+		for item in 0 .. 10
+		{
+			handle.push(item);
+		}
+		assert_eq!(handle.pop(), Some(9));
+	}
+	
+	assert_eq!(allocator.outstanding(), 0);
+}
+
+#[test]
+fn allocators_without_array_methods_allocate_and_free_arrays_by_default()
+{
+	fn cluster_of_current_thread() -> usize
+	{
+		2
+	}
+	
+	let allocator = CountingAllocatorWithoutArrays::default();
+	
+	{
+		let queue = CcQueue::configured(allocator.clone(), CcQueueConfiguration { clustering: Clustering::Explicit { clusters: 3, cluster_of_current_thread }, operation_records: 5, ..CcQueueConfiguration::default() });
+		queue.new_recoverable_per_thread_handle(4).enqueue(1).unwrap();
+		assert_eq!(queue.dequeue(), Ok(1));
+	}
+	
+	assert_eq!(allocator.0.outstanding(), 0);
+}
@@ -0,0 +1,188 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::DequeueError;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::Duration;
+
+
+#[test]
+fn batches_preserve_order()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	queue.enqueue_batch((0 .. 100).map(|item| item.to_string())).unwrap();
+	
+	let mut dequeued = Vec::new();
+	assert_eq!(queue.dequeue_batch(30, &mut dequeued), Ok(30));
+	assert_eq!(dequeued, (0 .. 30).map(|item| item.to_string()).collect::<Vec<_>>());
+	
+	assert_eq!(queue.dequeue_batch(1_000, &mut dequeued), Ok(70));
+	assert_eq!(dequeued, (0 .. 100).map(|item| item.to_string()).collect::<Vec<_>>());
+	
+	assert_eq!(queue.dequeue_batch(1_000, &mut dequeued), Err(DequeueError::Empty));
+	assert_eq!(dequeued.len(), 100);
+}
+
+#[test]
+fn a_batch_whose_iterator_uses_the_queue_is_enqueued_after_what_the_iterator_enqueues()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	queue.enqueue(0).unwrap();
+	
+	let batch = (1 .. 4).inspect(|&item|
+	{
+		queue.enqueue(item * 10).unwrap();
+		queue.enqueue_batch(vec![item * 10 + 1]).unwrap();
+		assert!(queue.dequeue().is_ok());
+	});
+	queue.enqueue_batch(batch).unwrap();
+	
+	let mut dequeued = Vec::new();
+	assert_eq!(queue.dequeue_batch(1_000, &mut dequeued), Ok(7));
+	assert_eq!(dequeued, vec![20, 21, 30, 31, 1, 2, 3]);
+}
+
+#[test]
+fn batches_interleave_with_single_items()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	let mut handle = queue.new_per_thread_handle();
+	
+	handle.enqueue(1).unwrap();
+	handle.enqueue_batch(vec![2, 3]).unwrap();
+	handle.enqueue(4).unwrap();
+	
+	let mut dequeued = Vec::new();
+	assert_eq!(handle.dequeue_batch(2, &mut dequeued), Ok(2));
+	assert_eq!(handle.dequeue(), Ok(3));
+	assert_eq!(handle.dequeue_batch(10, &mut dequeued), Ok(1));
+	assert_eq!(dequeued, vec![1, 2, 4]);
+	
+	handle.enqueue(5).unwrap();
+	assert_eq!(handle.dequeue_batch(1, &mut dequeued), Ok(1));
+	assert_eq!(handle.dequeue(), Err(DequeueError::Empty));
+	assert_eq!(dequeued, vec![1, 2, 4, 5]);
+}
+
+#[test]
+fn empty_batches_do_nothing()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	queue.enqueue_batch(Vec::<usize>::new()).unwrap();
+	queue.enqueue(1).unwrap();
+	
+	let mut dequeued = Vec::new();
+	assert_eq!(queue.dequeue_batch(0, &mut dequeued), Ok(0));
+	assert!(dequeued.is_empty());
+	assert_eq!(queue.dequeue(), Ok(1));
+}
+
+#[test]
+fn bounded_enqueue_batch_parks_until_there_is_space_for_the_rest()
+{
+	const ITEMS: usize = 10_000;
+	
+	let queue = Arc::new(CcQueue::bounded(HeapAllocator, 4));
+	
+	let producer =
+	{
+		let queue = queue.clone();
+		thread::spawn(move || queue.enqueue_batch(0 .. ITEMS).unwrap())
+	};
+	
+	let mut dequeued = Vec::new();
+	while dequeued.len() < ITEMS
+	{
+		match queue.dequeue_batch(3, &mut dequeued)
+		{
+			Ok(count) => assert!((1 ..= 3).contains(&count)),
+			Err(error) => assert_eq!(error, DequeueError::Empty),
+		}
+	}
+	
+	producer.join().unwrap();
+	assert_eq!(dequeued, (0 .. ITEMS).collect::<Vec<_>>());
+}
+
+#[test]
+fn enqueue_batch_returns_what_was_not_enqueued_when_closed()
+{
+	let queue = Arc::new(CcQueue::bounded(HeapAllocator, 2));
+	
+	let producer =
+	{
+		let queue = queue.clone();
+		thread::spawn(move || queue.enqueue_batch(0 .. 5))
+	};
+	
+	thread::sleep(Duration::from_millis(50));
+	queue.close();
+	
+	assert_eq!(producer.join().unwrap(), Err(vec![2, 3, 4]));
+	assert_eq!(queue.enqueue_batch(vec![5, 6]), Err(vec![5, 6]));
+	
+	let mut dequeued = Vec::new();
+	assert_eq!(queue.dequeue_batch(10, &mut dequeued), Ok(2));
+	assert_eq!(dequeued, vec![0, 1]);
+	assert_eq!(queue.dequeue_batch(10, &mut dequeued), Err(DequeueError::Closed));
+}
+
+#[test]
+fn many_batch_producers_and_consumers()
+{
+	const PRODUCERS: usize = 4;
+	const CONSUMERS: usize = 4;
+	const BATCHES_PER_PRODUCER: usize = 100;
+	const BATCH: usize = 100;
+	
+	let queue = Arc::new(CcQueue::bounded(HeapAllocator, 256));
+	
+	let producers: Vec<_> = (0 .. PRODUCERS).map(|producer|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			for batch in 0 .. BATCHES_PER_PRODUCER
+			{
+				let first = (producer * BATCHES_PER_PRODUCER + batch) * BATCH;
+				queue.enqueue_batch(first .. first + BATCH).unwrap()
+			}
+		})
+	}).collect();
+	
+	let consumers: Vec<_> = (0 .. CONSUMERS).map(|_|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			let mut dequeued = Vec::new();
+			loop
+			{
+				match queue.dequeue_batch(64, &mut dequeued)
+				{
+					Ok(_) => (),
+					Err(DequeueError::Empty) => thread::yield_now(),
+					Err(DequeueError::Closed) => return dequeued,
+					Err(DequeueError::TimedOut) => unreachable!("dequeue_batch() does not wait"),
+				}
+			}
+		})
+	}).collect();
+	
+	for producer in producers
+	{
+		producer.join().unwrap();
+	}
+	queue.close();
+	
+	let mut dequeued: Vec<usize> = consumers.into_iter().flat_map(|consumer| consumer.join().unwrap()).collect();
+	dequeued.sort();
+	assert_eq!(dequeued, (0 .. PRODUCERS * BATCHES_PER_PRODUCER * BATCH).collect::<Vec<_>>());
+}
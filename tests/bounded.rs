@@ -0,0 +1,141 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::DequeueError;
+use ::cc_queue::EnqueueError;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::sync::Arc;
+use ::std::sync::atomic::AtomicBool;
+use ::std::sync::atomic::Ordering::SeqCst;
+use ::std::thread;
+use ::std::time::Duration;
+
+
+#[test]
+fn try_enqueue_returns_data_when_full()
+{
+	let queue = CcQueue::bounded(HeapAllocator, 2);
+	
+	assert_eq!(queue.try_enqueue(1), Ok(()));
+	assert_eq!(queue.try_enqueue(2), Ok(()));
+	assert_eq!(queue.try_enqueue(3), Err(EnqueueError::Full(3)));
+	
+	assert_eq!(queue.dequeue(), Ok(1));
+	assert_eq!(queue.try_enqueue(3), Ok(()));
+	assert_eq!(queue.try_enqueue(4), Err(EnqueueError::Full(4)));
+	
+	assert_eq!(queue.dequeue(), Ok(2));
+	assert_eq!(queue.dequeue(), Ok(3));
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+}
+
+#[test]
+fn try_enqueue_on_explicit_handle_returns_data_when_full()
+{
+	let queue = CcQueue::bounded(HeapAllocator, 1);
+	let mut handle = queue.new_per_thread_handle();
+	
+	assert_eq!(handle.try_enqueue("first".to_string()), Ok(()));
+	assert_eq!(handle.try_enqueue("second".to_string()), Err(EnqueueError::Full("second".to_string())));
+	assert_eq!(handle.dequeue(), Ok("first".to_string()));
+	assert_eq!(handle.try_enqueue("second".to_string()), Ok(()));
+}
+
+#[test]
+fn unbounded_queue_is_never_full()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	for item in 0 .. 10_000
+	{
+		assert_eq!(queue.try_enqueue(item), Ok(()));
+	}
+}
+
+#[test]
+fn clear_makes_space()
+{
+	let mut queue = CcQueue::configured(HeapAllocator, CcQueueConfiguration { capacity: Some(1), ..CcQueueConfiguration::default() });
+	queue.enqueue(1).unwrap();
+	assert_eq!(queue.try_enqueue(2), Err(EnqueueError::Full(2)));
+	
+	queue.clear(drop);
+	assert_eq!(queue.try_enqueue(2), Ok(()));
+}
+
+#[test]
+#[should_panic]
+fn zero_capacity_is_not_permitted()
+{
+	let _queue: CcQueue<usize, HeapAllocator> = CcQueue::bounded(HeapAllocator, 0);
+}
+
+#[test]
+fn enqueue_parks_until_there_is_space()
+{
+	let queue = Arc::new(CcQueue::bounded(HeapAllocator, 1));
+	queue.enqueue(1).unwrap();
+	
+	let enqueued = Arc::new(AtomicBool::new(false));
+	let producer =
+	{
+		let queue = queue.clone();
+		let enqueued = enqueued.clone();
+		thread::spawn(move ||
+		{
+			queue.new_per_thread_handle().enqueue(2).unwrap();
+			enqueued.store(true, SeqCst)
+		})
+	};
+	
+	thread::sleep(Duration::from_millis(50));
+	assert!(!enqueued.load(SeqCst));
+	
+	assert_eq!(queue.dequeue(), Ok(1));
+	producer.join().unwrap();
+	assert!(enqueued.load(SeqCst));
+	assert_eq!(queue.dequeue(), Ok(2));
+}
+
+#[test]
+fn many_blocked_producers_and_consumers()
+{
+	const PRODUCERS: usize = 4;
+	const CONSUMERS: usize = 4;
+	const ITEMS_PER_PRODUCER: usize = 5_000;
+	const CAPACITY: usize = 8;
+	
+	let queue = Arc::new(CcQueue::bounded(HeapAllocator, CAPACITY));
+	
+	let producers: Vec<_> = (0 .. PRODUCERS).map(|producer|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			for index in 0 .. ITEMS_PER_PRODUCER
+			{
+				queue.enqueue(producer * ITEMS_PER_PRODUCER + index).unwrap()
+			}
+		})
+	}).collect();
+	
+	let consumers: Vec<_> = (0 .. CONSUMERS).map(|_|
+	{
+		let queue = queue.clone();
+		thread::spawn(move || (0 .. PRODUCERS * ITEMS_PER_PRODUCER / CONSUMERS).map(|_| queue.dequeue_wait().unwrap()).collect::<Vec<usize>>())
+	}).collect();
+	
+	for producer in producers
+	{
+		producer.join().unwrap();
+	}
+	
+	let mut dequeued: Vec<usize> = consumers.into_iter().flat_map(|consumer| consumer.join().unwrap()).collect();
+	dequeued.sort();
+	assert_eq!(dequeued, (0 .. PRODUCERS * ITEMS_PER_PRODUCER).collect::<Vec<_>>());
+}
@@ -0,0 +1,120 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcSynch;
+use ::std::collections::BinaryHeap;
+use ::std::collections::HashMap;
+use ::std::sync::Arc;
+use ::std::thread;
+
+
+#[test]
+fn apply_returns_the_response()
+{
+	let synch = CcSynch::new(Vec::new());
+	let mut handle = synch.new_per_thread_handle();
+	
+	assert_eq!(handle.apply(|state| { state.push(1); state.len() }), 1);
+	assert_eq!(handle.apply(|state| { state.push(2); state.len() }), 2);
+	assert_eq!(handle.apply(|state| state.pop()), Some(2));
+	
+	drop(handle);
+	assert_eq!(synch.into_inner(), vec![1]);
+}
+
+#[test]
+fn combining_counter_hands_out_each_value_once()
+{
+	const THREADS: usize = 8;
+	const INCREMENTS_PER_THREAD: usize = 10_000;
+	
+	let synch = Arc::new(CcSynch::new(0usize));
+	
+	let threads: Vec<_> = (0 .. THREADS).map(|_|
+	{
+		let synch = synch.clone();
+		thread::spawn(move ||
+		{
+			let mut handle = synch.new_per_thread_handle();
+			(0 .. INCREMENTS_PER_THREAD).map(|_| handle.apply(|counter| { let previous = *counter; *counter += 1; previous })).collect::<Vec<_>>()
+		})
+	}).collect();
+	
+	let mut values: Vec<usize> = threads.into_iter().flat_map(|thread| thread.join().unwrap()).collect();
+	values.sort();
+	assert_eq!(values, (0 .. THREADS * INCREMENTS_PER_THREAD).collect::<Vec<_>>());
+	
+	let mut synch = Arc::try_unwrap(synch).unwrap();
+	assert_eq!(*synch.get_mut(), THREADS * INCREMENTS_PER_THREAD);
+}
+
+#[test]
+fn operations_of_different_types_on_a_combining_map()
+{
+	const THREADS: usize = 4;
+	const KEYS_PER_THREAD: usize = 1_000;
+	
+	let synch: Arc<CcSynch<HashMap<usize, String>>> = Arc::new(CcSynch::default());
+	
+	let threads: Vec<_> = (0 .. THREADS).map(|thread|
+	{
+		let synch = synch.clone();
+		thread::spawn(move ||
+		{
+			let mut handle = synch.new_per_thread_handle();
+			for index in 0 .. KEYS_PER_THREAD
+			{
+				let key = thread * KEYS_PER_THREAD + index;
+				let value = key.to_string();
+				assert_eq!(handle.apply(move |map| map.insert(key, value)), None);
+				assert_eq!(handle.apply(|map| map.get(&key).cloned()), Some(key.to_string()));
+			}
+		})
+	}).collect();
+	
+	for thread in threads
+	{
+		thread.join().unwrap();
+	}
+	
+	assert_eq!(Arc::try_unwrap(synch).unwrap().into_inner().len(), THREADS * KEYS_PER_THREAD);
+}
+
+#[test]
+fn combining_heap()
+{
+	const THREADS: usize = 4;
+	const ITEMS_PER_THREAD: usize = 1_000;
+	
+	let synch = Arc::new(CcSynch::new(BinaryHeap::new()));
+	
+	let threads: Vec<_> = (0 .. THREADS).map(|thread|
+	{
+		let synch = synch.clone();
+		thread::spawn(move ||
+		{
+			let mut handle = synch.new_per_thread_handle();
+			for index in 0 .. ITEMS_PER_THREAD
+			{
+				handle.apply(move |heap| heap.push(thread * ITEMS_PER_THREAD + index))
+			}
+		})
+	}).collect();
+	
+	for thread in threads
+	{
+		thread.join().unwrap();
+	}
+	
+	let mut handle = synch.new_per_thread_handle();
+	let mut popped = Vec::new();
+	while let Some(item) = handle.apply(|heap| heap.pop())
+	{
+		popped.push(item)
+	}
+	assert_eq!(popped, (0 .. THREADS * ITEMS_PER_THREAD).rev().collect::<Vec<_>>());
+}
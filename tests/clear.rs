@@ -0,0 +1,202 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::DequeueError;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::cell::Cell;
+
+
+thread_local!
+{
+	static DROPPED: Cell<usize> = const { Cell::new(0) };
+}
+
+fn dropped() -> usize
+{
+	DROPPED.with(|dropped| dropped.get())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Counted(usize);
+
+impl Drop for Counted
+{
+	fn drop(&mut self)
+	{
+		DROPPED.with(|dropped| dropped.set(dropped.get() + 1))
+	}
+}
+
+/// Enqueues `enqueue` items then dequeues `dequeue` of them, and checks that clearing frees each remaining item exactly once, in order.
+fn clear_after(enqueue: usize, dequeue: usize)
+{
+	let before = dropped();
+	
+	let mut queue = CcQueue::new(HeapAllocator);
+	{
+		let mut handle = queue.new_per_thread_handle();
+		for index in 0 .. enqueue
+		{
+			handle.enqueue(Counted(index)).unwrap();
+		}
+		for index in 0 .. dequeue
+		{
+			assert_eq!(handle.dequeue().map(|item| item.0), Ok(index));
+		}
+	}
+	assert_eq!(dropped() - before, dequeue);
+	
+	let mut expected = dequeue;
+	queue.clear(|item|
+	{
+		assert_eq!(item.0, expected);
+		expected += 1;
+	});
+	assert_eq!(expected, enqueue);
+	assert_eq!(dropped() - before, enqueue);
+	
+	// The queue is still usable after being cleared.
+	{
+		let mut handle = queue.new_per_thread_handle();
+		assert_eq!(handle.dequeue(), Err(DequeueError::Empty));
+		handle.enqueue(Counted(enqueue)).unwrap();
+		assert_eq!(handle.dequeue().map(|item| item.0), Ok(enqueue));
+		assert_eq!(handle.dequeue(), Err(DequeueError::Empty));
+	}
+	
+	drop(queue);
+	assert_eq!(dropped() - before, enqueue + 1);
+}
+
+#[test]
+fn clear_empty_queue_with_head_and_tail_at_initial_dummy_node()
+{
+	clear_after(0, 0)
+}
+
+#[test]
+fn clear_queue_with_tail_immediately_after_head()
+{
+	clear_after(1, 0)
+}
+
+#[test]
+fn clear_queue_with_tail_several_nodes_after_head()
+{
+	clear_after(3, 0)
+}
+
+#[test]
+fn clear_drained_queue_with_head_and_tail_at_later_dummy_node()
+{
+	clear_after(3, 3)
+}
+
+#[test]
+fn clear_partially_drained_queue()
+{
+	clear_after(5, 2)
+}
+
+#[test]
+fn clear_twice()
+{
+	let mut queue = CcQueue::new(HeapAllocator);
+	{
+		let mut handle = queue.new_per_thread_handle();
+		handle.enqueue(1).unwrap();
+		handle.enqueue(2).unwrap();
+	}
+	
+	let mut cleared = Vec::new();
+	queue.clear(|item| cleared.push(item));
+	queue.clear(|item| cleared.push(item));
+	assert_eq!(cleared, vec![1, 2]);
+}
+
+#[test]
+fn drop_queue_runs_destructor_on_each_remaining_item_exactly_once()
+{
+	let before = dropped();
+	
+	let queue = CcQueue::new(HeapAllocator);
+	{
+		let mut handle = queue.new_per_thread_handle();
+		for index in 0 .. 10
+		{
+			handle.enqueue(Counted(index)).unwrap();
+		}
+	}
+	drop(queue);
+	
+	assert_eq!(dropped() - before, 10);
+}
+
+#[test]
+fn drop_partially_drained_queue_runs_destructor_on_each_remaining_item_exactly_once()
+{
+	let before = dropped();
+	
+	let queue = CcQueue::new(HeapAllocator);
+	{
+		let mut handle = queue.new_per_thread_handle();
+		for index in 0 .. 5
+		{
+			handle.enqueue(Counted(index)).unwrap();
+		}
+		for index in 0 .. 2
+		{
+			assert_eq!(handle.dequeue().map(|item| item.0), Ok(index));
+		}
+	}
+	assert_eq!(dropped() - before, 2);
+	
+	drop(queue);
+	assert_eq!(dropped() - before, 5);
+}
+
+const TEN_MILLION: usize = 10_000_000;
+
+#[test]
+fn clear_ten_million_items_without_overflowing_the_stack()
+{
+	let mut queue = CcQueue::new(HeapAllocator);
+	{
+		let mut handle = queue.new_per_thread_handle();
+		for index in 0 .. TEN_MILLION
+		{
+			handle.enqueue(index).unwrap();
+		}
+	}
+	
+	let mut expected = 0;
+	queue.clear(|item|
+	{
+		assert_eq!(item, expected);
+		expected += 1;
+	});
+	assert_eq!(expected, TEN_MILLION);
+}
+
+#[test]
+fn drop_ten_million_items_without_overflowing_the_stack()
+{
+	let before = dropped();
+	
+	let queue = CcQueue::new(HeapAllocator);
+	{
+		let mut handle = queue.new_per_thread_handle();
+		for index in 0 .. TEN_MILLION
+		{
+			handle.enqueue(Counted(index)).unwrap();
+		}
+	}
+	drop(queue);
+	
+	assert_eq!(dropped() - before, TEN_MILLION);
+}
@@ -0,0 +1,150 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::DequeueError;
+use ::cc_queue::EnqueueError;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::Duration;
+
+
+#[test]
+fn close_rejects_enqueues_and_drains_before_reporting_closed()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	queue.enqueue(1).unwrap();
+	queue.enqueue(2).unwrap();
+	assert!(!queue.is_closed());
+	
+	queue.close();
+	assert!(queue.is_closed());
+	
+	assert_eq!(queue.enqueue(3), Err(3));
+	assert_eq!(queue.try_enqueue(3), Err(EnqueueError::Closed(3)));
+	
+	assert_eq!(queue.dequeue(), Ok(1));
+	assert_eq!(queue.dequeue(), Ok(2));
+	assert_eq!(queue.dequeue(), Err(DequeueError::Closed));
+	assert_eq!(queue.dequeue(), Err(DequeueError::Closed));
+	assert_eq!(queue.dequeue_wait(), None);
+	assert_eq!(queue.dequeue_timeout(Duration::from_secs(60)), Err(DequeueError::Closed));
+}
+
+#[test]
+fn close_on_explicit_handle()
+{
+	let queue = CcQueue::bounded(HeapAllocator, 1);
+	let mut handle = queue.new_per_thread_handle();
+	
+	handle.enqueue("first".to_string()).unwrap();
+	assert_eq!(handle.try_enqueue("second".to_string()), Err(EnqueueError::Full("second".to_string())));
+	
+	handle.close();
+	handle.close();
+	assert_eq!(handle.try_enqueue("second".to_string()), Err(EnqueueError::Closed("second".to_string())));
+	assert_eq!(handle.dequeue(), Ok("first".to_string()));
+	assert_eq!(handle.dequeue(), Err(DequeueError::Closed));
+}
+
+#[test]
+fn enqueue_error_returns_data()
+{
+	assert_eq!(EnqueueError::Full(1).into_inner(), 1);
+	assert_eq!(EnqueueError::Closed(2).into_inner(), 2);
+	assert!(EnqueueError::Full(1).is_full());
+	assert!(EnqueueError::Closed(1).is_closed());
+}
+
+#[test]
+fn close_wakes_parked_consumers()
+{
+	const CONSUMERS: usize = 4;
+	
+	let queue = Arc::new(CcQueue::<usize, _>::new(HeapAllocator));
+	
+	let consumers: Vec<_> = (0 .. CONSUMERS).map(|_|
+	{
+		let queue = queue.clone();
+		thread::spawn(move || queue.dequeue_wait())
+	}).collect();
+	
+	thread::sleep(Duration::from_millis(50));
+	queue.close();
+	
+	for consumer in consumers
+	{
+		assert_eq!(consumer.join().unwrap(), None);
+	}
+}
+
+#[test]
+fn close_wakes_blocked_producers()
+{
+	let queue = Arc::new(CcQueue::bounded(HeapAllocator, 1));
+	queue.enqueue(1).unwrap();
+	
+	let producer =
+	{
+		let queue = queue.clone();
+		thread::spawn(move || queue.enqueue(2))
+	};
+	
+	thread::sleep(Duration::from_millis(50));
+	queue.close();
+	
+	assert_eq!(producer.join().unwrap(), Err(2));
+	assert_eq!(queue.dequeue(), Ok(1));
+	assert_eq!(queue.dequeue(), Err(DequeueError::Closed));
+}
+
+#[test]
+fn closed_queue_is_a_multi_producer_multi_consumer_channel()
+{
+	const PRODUCERS: usize = 4;
+	const CONSUMERS: usize = 4;
+	const ITEMS_PER_PRODUCER: usize = 5_000;
+	
+	let queue = Arc::new(CcQueue::bounded(HeapAllocator, 16));
+	
+	let producers: Vec<_> = (0 .. PRODUCERS).map(|producer|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			for index in 0 .. ITEMS_PER_PRODUCER
+			{
+				queue.enqueue(producer * ITEMS_PER_PRODUCER + index).unwrap()
+			}
+		})
+	}).collect();
+	
+	let consumers: Vec<_> = (0 .. CONSUMERS).map(|_|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			let mut dequeued = Vec::new();
+			while let Some(item) = queue.dequeue_wait()
+			{
+				dequeued.push(item)
+			}
+			dequeued
+		})
+	}).collect();
+	
+	for producer in producers
+	{
+		producer.join().unwrap();
+	}
+	queue.close();
+	
+	let mut dequeued: Vec<usize> = consumers.into_iter().flat_map(|consumer| consumer.join().unwrap()).collect();
+	dequeued.sort();
+	assert_eq!(dequeued, (0 .. PRODUCERS * ITEMS_PER_PRODUCER).collect::<Vec<_>>());
+}
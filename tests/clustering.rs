@@ -0,0 +1,188 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::Clustering;
+use ::cc_queue::DequeueError;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::cell::Cell;
+use ::std::env::temp_dir;
+use ::std::fs::create_dir_all;
+use ::std::fs::remove_dir_all;
+use ::std::fs::write;
+use ::std::path::PathBuf;
+use ::std::process;
+use ::std::sync::Arc;
+use ::std::thread;
+
+
+thread_local!
+{
+	static CLUSTER: Cell<usize> = const { Cell::new(0) };
+}
+
+fn cluster_of_current_thread() -> usize
+{
+	CLUSTER.with(|cluster| cluster.get())
+}
+
+fn clustered(clustering: Clustering) -> CcQueue<usize, HeapAllocator>
+{
+	CcQueue::configured(HeapAllocator, CcQueueConfiguration { clustering, ..CcQueueConfiguration::default() })
+}
+
+fn fake_sysfs_numa_nodes(name: &str, nodes: &[(&str, &str)]) -> PathBuf
+{
+	let path = temp_dir().join(format!("cc-queue-clustering-{}-{}", process::id(), name));
+	let _ = remove_dir_all(&path);
+	create_dir_all(&path).unwrap();
+	write(path.join("possible"), "0-7\n").unwrap();
+	
+	for &(node, cpu_list) in nodes
+	{
+		let node = path.join(node);
+		create_dir_all(&node).unwrap();
+		write(node.join("cpulist"), cpu_list).unwrap();
+	}
+	path
+}
+
+#[test]
+fn single_cluster_by_default()
+{
+	let queue = CcQueue::<usize, _>::new(HeapAllocator);
+	assert_eq!(queue.clusters(), 1);
+	assert_eq!(queue.new_per_thread_handle().cluster(), 0);
+}
+
+#[test]
+fn explicit_mapping_decides_cluster_of_each_handle()
+{
+	let queue = clustered(Clustering::Explicit { clusters: 3, cluster_of_current_thread });
+	assert_eq!(queue.clusters(), 3);
+	
+	for cluster in 0 .. 7
+	{
+		CLUSTER.with(|current| current.set(cluster));
+		assert_eq!(queue.new_per_thread_handle().cluster(), cluster % 3);
+	}
+}
+
+#[test]
+#[should_panic]
+fn zero_explicit_clusters_is_not_permitted()
+{
+	clustered(Clustering::Explicit { clusters: 0, cluster_of_current_thread });
+}
+
+#[test]
+fn numa_nodes_are_read_from_sysfs_layout()
+{
+	let path = fake_sysfs_numa_nodes("two", &[("node0", "0-1,4\n"), ("node2", "2-3,5-511\n"), ("node3", "\n")]);
+	
+	let queue = clustered(Clustering::NumaNodes(path.clone()));
+	assert_eq!(queue.clusters(), 2);
+	assert!(queue.new_per_thread_handle().cluster() < 2);
+	
+	remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn one_numa_node_is_a_single_cluster()
+{
+	let path = fake_sysfs_numa_nodes("one", &[("node0", "0-511\n")]);
+	assert_eq!(clustered(Clustering::NumaNodes(path.clone())).clusters(), 1);
+	remove_dir_all(path).unwrap();
+}
+
+#[test]
+fn missing_numa_nodes_is_a_single_cluster()
+{
+	let path = temp_dir().join(format!("cc-queue-clustering-{}-missing", process::id()));
+	assert_eq!(clustered(Clustering::NumaNodes(path)).clusters(), 1);
+}
+
+#[test]
+fn numa_nodes_of_this_machine()
+{
+	let queue = clustered(Clustering::numa_nodes());
+	assert!(queue.clusters() >= 1);
+	
+	queue.enqueue(1).unwrap();
+	assert_eq!(queue.dequeue(), Ok(1));
+}
+
+#[test]
+fn many_threads_in_many_clusters_keep_each_producers_order()
+{
+	const CLUSTERS: usize = 4;
+	const PRODUCERS: usize = 8;
+	const ITEMS_PER_PRODUCER: usize = 10_000;
+	
+	let queue = Arc::new(clustered(Clustering::Explicit { clusters: CLUSTERS, cluster_of_current_thread }));
+	
+	let producers: Vec<_> = (0 .. PRODUCERS).map(|producer|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			CLUSTER.with(|cluster| cluster.set(producer));
+			
+			let mut handle = queue.new_per_thread_handle();
+			assert_eq!(handle.cluster(), producer % CLUSTERS);
+			for index in 0 .. ITEMS_PER_PRODUCER
+			{
+				if index % 2 == 0
+				{
+					handle.enqueue(producer * ITEMS_PER_PRODUCER + index).unwrap()
+				}
+				else
+				{
+					queue.enqueue(producer * ITEMS_PER_PRODUCER + index).unwrap()
+				}
+			}
+		})
+	}).collect();
+	
+	let consumer =
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			CLUSTER.with(|cluster| cluster.set(1));
+			
+			let mut next = [0; PRODUCERS];
+			let mut dequeued = 0;
+			while dequeued < PRODUCERS * ITEMS_PER_PRODUCER
+			{
+				match queue.dequeue()
+				{
+					Ok(item) =>
+					{
+						let producer = item / ITEMS_PER_PRODUCER;
+						assert_eq!(item % ITEMS_PER_PRODUCER, next[producer]);
+						next[producer] += 1;
+						dequeued += 1
+					}
+					Err(error) =>
+					{
+						assert_eq!(error, DequeueError::Empty);
+						thread::yield_now()
+					}
+				}
+			}
+		})
+	};
+	
+	for producer in producers
+	{
+		producer.join().unwrap();
+	}
+	consumer.join().unwrap();
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+}
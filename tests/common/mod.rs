@@ -0,0 +1,97 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+// Helpers shared by the tests that use files; not every test uses every helper.
+#![allow(dead_code)]
+
+
+use ::std::env;
+use ::std::fs::remove_file;
+use ::std::io::BufRead;
+use ::std::io::BufReader;
+use ::std::io::Write;
+use ::std::io::stdout;
+use ::std::path::Path;
+use ::std::path::PathBuf;
+use ::std::process;
+use ::std::process::Command;
+use ::std::process::Stdio;
+use ::std::thread;
+use ::std::time::Duration;
+use ::std::time::SystemTime;
+use ::std::time::UNIX_EPOCH;
+
+
+// Set for a child process spawned by `kill_children()`.
+const CHILD_PATH: &str = "CC_QUEUE_CHILD_PATH";
+
+
+// Removed when dropped.
+pub struct TemporaryPath(pub PathBuf);
+
+impl Drop for TemporaryPath
+{
+	fn drop(&mut self)
+	{
+		let _ = remove_file(&self.0);
+	}
+}
+
+impl TemporaryPath
+{
+	pub fn new(name: &str) -> Self
+	{
+		let path = env::temp_dir().join(format!("cc-queue-{}-{}", process::id(), name));
+		let _ = remove_file(&path);
+		TemporaryPath(path)
+	}
+}
+
+// Called by a child process's `body` once it has opened the queue in its file, after which it may be killed.
+pub fn ready()
+{
+	println!("ready");
+	stdout().flush().unwrap();
+}
+
+// For the test `test`: `kills` times, calls `create` to create a queue in a file, spawns a child process that calls `body` to use that queue until it is killed, kills it after a random delay, and calls `check` to recover the queue and check it.
+// The child process is this test, run again; `body` is called instead when this is that child process.
+pub fn kill_children(test: &str, kills: usize, create: impl Fn(&Path), body: fn(&Path) -> !, check: impl Fn(&Path))
+{
+	if let Some(path) = env::var_os(CHILD_PATH)
+	{
+		body(Path::new(&path))
+	}
+	
+	let path = TemporaryPath::new(test);
+	let mut random_delay = random_delays();
+	
+	for _ in 0 .. kills
+	{
+		let _ = remove_file(&path.0);
+		create(&path.0);
+		
+		let mut child = Command::new(env::current_exe().unwrap()).args(["--exact", test, "--nocapture", "--test-threads", "1"]).env(CHILD_PATH, &path.0).stdout(Stdio::piped()).spawn().unwrap();
+		let ready = BufReader::new(child.stdout.take().unwrap()).lines().any(|line| line.unwrap().ends_with("ready"));
+		assert!(ready, "child exited before it was ready");
+		thread::sleep(random_delay());
+		child.kill().unwrap();
+		child.wait().unwrap();
+		
+		check(&path.0);
+	}
+}
+
+// A xorshift generator, seeded from the time.
+fn random_delays() -> impl FnMut() -> Duration
+{
+	let mut state = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().subsec_nanos() as u64 | 1;
+	move ||
+	{
+		state ^= state << 13;
+		state ^= state >> 7;
+		state ^= state << 17;
+		Duration::from_micros(state % 20_000)
+	}
+}
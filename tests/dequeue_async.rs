@@ -0,0 +1,188 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::future::Future;
+use ::std::pin::Pin;
+use ::std::sync::Arc;
+use ::std::sync::atomic::AtomicUsize;
+use ::std::sync::atomic::Ordering::SeqCst;
+use ::std::task::Context;
+use ::std::task::Poll;
+use ::std::task::Wake;
+use ::std::task::Waker;
+use ::std::thread;
+use ::std::thread::Thread;
+use ::std::time::Duration;
+
+
+struct ThreadWaker
+{
+	thread: Thread,
+	woken: AtomicUsize,
+}
+
+impl Wake for ThreadWaker
+{
+	fn wake(self: Arc<Self>)
+	{
+		self.woken.fetch_add(1, SeqCst);
+		self.thread.unpark()
+	}
+}
+
+fn thread_waker() -> (Arc<ThreadWaker>, Waker)
+{
+	let thread_waker = Arc::new(ThreadWaker { thread: thread::current(), woken: AtomicUsize::new(0) });
+	let waker = Waker::from(thread_waker.clone());
+	(thread_waker, waker)
+}
+
+/// A minimal executor, so that the tests do not depend on any particular runtime.
+fn block_on<F: Future>(future: F) -> F::Output
+{
+	let (_, waker) = thread_waker();
+	let mut context = Context::from_waker(&waker);
+	
+	let mut future = Box::pin(future);
+	loop
+	{
+		if let Poll::Ready(output) = future.as_mut().poll(&mut context)
+		{
+			return output
+		}
+		thread::park()
+	}
+}
+
+#[test]
+fn dequeue_async_is_ready_immediately_if_not_empty()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	queue.enqueue(1).unwrap();
+	assert_eq!(block_on(queue.dequeue_async()), Some(1));
+}
+
+#[test]
+fn dequeue_async_is_woken_by_enqueue()
+{
+	let queue = Arc::new(CcQueue::new(HeapAllocator));
+	
+	let producer =
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			thread::sleep(Duration::from_millis(50));
+			queue.enqueue(42).unwrap()
+		})
+	};
+	
+	assert_eq!(block_on(queue.dequeue_async()), Some(42));
+	producer.join().unwrap();
+}
+
+#[test]
+fn pending_dequeue_async_registers_its_waker_and_is_woken_once()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	let (thread_waker, waker) = thread_waker();
+	let mut context = Context::from_waker(&waker);
+	
+	let mut future = queue.dequeue_async();
+	assert_eq!(Pin::new(&mut future).poll(&mut context), Poll::Pending);
+	assert_eq!(Pin::new(&mut future).poll(&mut context), Poll::Pending);
+	assert_eq!(thread_waker.woken.load(SeqCst), 0);
+	
+	queue.enqueue(1).unwrap();
+	queue.enqueue(2).unwrap();
+	assert_eq!(thread_waker.woken.load(SeqCst), 1);
+	
+	assert_eq!(Pin::new(&mut future).poll(&mut context), Poll::Ready(Some(1)));
+	assert_eq!(queue.dequeue(), Ok(2));
+}
+
+#[test]
+fn dropping_a_woken_dequeue_async_passes_the_wake_on()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	let (first_thread_waker, first_waker) = thread_waker();
+	let (second_thread_waker, second_waker) = thread_waker();
+	
+	let mut first = queue.dequeue_async();
+	let mut second = queue.dequeue_async();
+	assert_eq!(Pin::new(&mut first).poll(&mut Context::from_waker(&first_waker)), Poll::Pending);
+	assert_eq!(Pin::new(&mut second).poll(&mut Context::from_waker(&second_waker)), Poll::Pending);
+	
+	queue.enqueue(1).unwrap();
+	assert_eq!(first_thread_waker.woken.load(SeqCst), 1);
+	assert_eq!(second_thread_waker.woken.load(SeqCst), 0);
+	
+	drop(first);
+	assert_eq!(second_thread_waker.woken.load(SeqCst), 1);
+	assert_eq!(Pin::new(&mut second).poll(&mut Context::from_waker(&second_waker)), Poll::Ready(Some(1)));
+}
+
+#[test]
+fn dequeue_stream_yields_each_item()
+{
+	const ITEMS: usize = 10_000;
+	
+	let queue = Arc::new(CcQueue::new(HeapAllocator));
+	
+	let producer =
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			for item in 0 .. ITEMS
+			{
+				queue.enqueue(item).unwrap()
+			}
+		})
+	};
+	
+	let mut stream = queue.dequeue_stream();
+	for expected in 0 .. ITEMS
+	{
+		let next = block_on(::std::future::poll_fn(|context| Pin::new(&mut stream).poll_next(context)));
+		assert_eq!(next, Some(expected));
+	}
+	
+	producer.join().unwrap();
+}
+
+#[test]
+fn close_wakes_pending_dequeue_async()
+{
+	let queue = CcQueue::<usize, _>::new(HeapAllocator);
+	let (thread_waker, waker) = thread_waker();
+	let mut context = Context::from_waker(&waker);
+	
+	let mut future = queue.dequeue_async();
+	assert_eq!(Pin::new(&mut future).poll(&mut context), Poll::Pending);
+	
+	queue.close();
+	assert_eq!(thread_waker.woken.load(SeqCst), 1);
+	assert_eq!(Pin::new(&mut future).poll(&mut context), Poll::Ready(None));
+}
+
+#[test]
+fn dequeue_stream_ends_once_closed_and_drained()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	queue.enqueue(1).unwrap();
+	queue.enqueue(2).unwrap();
+	queue.close();
+	
+	let mut stream = queue.dequeue_stream();
+	let mut next = || block_on(::std::future::poll_fn(|context| Pin::new(&mut stream).poll_next(context)));
+	assert_eq!(next(), Some(1));
+	assert_eq!(next(), Some(2));
+	assert_eq!(next(), None);
+}
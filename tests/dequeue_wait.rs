@@ -0,0 +1,156 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::DequeueError;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::Duration;
+use ::std::time::Instant;
+
+
+#[test]
+fn dequeue_wait_returns_immediately_if_not_empty()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	queue.enqueue(1).unwrap();
+	assert_eq!(queue.dequeue_wait(), Some(1));
+	
+	let mut handle = queue.new_per_thread_handle();
+	handle.enqueue(2).unwrap();
+	assert_eq!(handle.dequeue_wait(), Some(2));
+}
+
+#[test]
+fn dequeue_wait_parks_until_enqueue()
+{
+	let queue = Arc::new(CcQueue::new(HeapAllocator));
+	
+	let consumer =
+	{
+		let queue = queue.clone();
+		thread::spawn(move || queue.new_per_thread_handle().dequeue_wait())
+	};
+	
+	thread::sleep(Duration::from_millis(50));
+	queue.enqueue(42).unwrap();
+	
+	assert_eq!(consumer.join().unwrap(), Some(42));
+}
+
+#[test]
+fn every_parked_consumer_is_woken()
+{
+	const CONSUMERS: usize = 8;
+	const ITEMS_PER_CONSUMER: usize = 1_000;
+	
+	let queue = Arc::new(CcQueue::new(HeapAllocator));
+	
+	let consumers: Vec<_> = (0 .. CONSUMERS).map(|_|
+	{
+		let queue = queue.clone();
+		thread::spawn(move || (0 .. ITEMS_PER_CONSUMER).map(|_| queue.dequeue_wait().unwrap()).collect::<Vec<usize>>())
+	}).collect();
+	
+	thread::sleep(Duration::from_millis(50));
+	for item in 0 .. CONSUMERS * ITEMS_PER_CONSUMER
+	{
+		queue.enqueue(item).unwrap();
+		if item % 100 == 0
+		{
+			thread::yield_now()
+		}
+	}
+	
+	let mut dequeued: Vec<usize> = consumers.into_iter().flat_map(|consumer| consumer.join().unwrap()).collect();
+	dequeued.sort();
+	assert_eq!(dequeued, (0 .. CONSUMERS * ITEMS_PER_CONSUMER).collect::<Vec<_>>());
+}
+
+#[test]
+fn consumers_parked_on_an_empty_queue_are_each_woken_by_enqueues_after_the_first()
+{
+	const CONSUMERS: usize = 8;
+	
+	let queue = Arc::new(CcQueue::new(HeapAllocator));
+	
+	let consumers: Vec<_> = (0 .. CONSUMERS).map(|_|
+	{
+		let queue = queue.clone();
+		thread::spawn(move || queue.dequeue_timeout(Duration::from_secs(60)))
+	}).collect();
+	
+	// Only the first enqueue finds the queue empty, so the others are passed on by consumers.
+	thread::sleep(Duration::from_millis(50));
+	for item in 0 .. CONSUMERS
+	{
+		queue.enqueue(item).unwrap();
+	}
+	
+	let mut dequeued: Vec<usize> = consumers.into_iter().map(|consumer| consumer.join().unwrap().unwrap()).collect();
+	dequeued.sort();
+	assert_eq!(dequeued, (0 .. CONSUMERS).collect::<Vec<_>>());
+}
+
+#[test]
+fn dequeue_timeout_times_out_after_timeout()
+{
+	let queue: CcQueue<usize, HeapAllocator> = CcQueue::new(HeapAllocator);
+	
+	let started = Instant::now();
+	assert_eq!(queue.dequeue_timeout(Duration::from_millis(50)), Err(DequeueError::TimedOut));
+	assert!(started.elapsed() >= Duration::from_millis(50));
+	
+	assert_eq!(queue.new_per_thread_handle().dequeue_timeout(Duration::from_millis(0)), Err(DequeueError::TimedOut));
+}
+
+#[test]
+fn dequeue_deadline_times_out_after_deadline()
+{
+	let queue: CcQueue<usize, HeapAllocator> = CcQueue::new(HeapAllocator);
+	
+	let deadline = Instant::now() + Duration::from_millis(50);
+	assert_eq!(queue.new_per_thread_handle().dequeue_deadline(deadline), Err(DequeueError::TimedOut));
+	assert!(Instant::now() >= deadline);
+	
+	assert_eq!(queue.dequeue_deadline(Instant::now()), Err(DequeueError::TimedOut));
+}
+
+#[test]
+fn dequeue_timeout_returns_data_enqueued_before_timeout()
+{
+	let queue = Arc::new(CcQueue::new(HeapAllocator));
+	
+	let consumer =
+	{
+		let queue = queue.clone();
+		thread::spawn(move || queue.dequeue_timeout(Duration::from_secs(60)))
+	};
+	
+	thread::sleep(Duration::from_millis(50));
+	queue.enqueue(42).unwrap();
+	
+	assert_eq!(consumer.join().unwrap(), Ok(42));
+}
+
+#[test]
+fn dequeue_timeout_with_unrepresentable_timeout_waits_forever()
+{
+	let queue = Arc::new(CcQueue::new(HeapAllocator));
+	
+	let consumer =
+	{
+		let queue = queue.clone();
+		thread::spawn(move || queue.new_per_thread_handle().dequeue_timeout(Duration::new(u64::MAX, 0)))
+	};
+	
+	thread::sleep(Duration::from_millis(50));
+	queue.enqueue(42).unwrap();
+	
+	assert_eq!(consumer.join().unwrap(), Ok(42));
+}
@@ -0,0 +1,196 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::Clustering;
+use ::cc_queue::DequeueError;
+use ::cc_queue::DsmSynchStrategy;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::cell::Cell;
+use ::std::sync::Arc;
+use ::std::sync::atomic::AtomicUsize;
+use ::std::sync::atomic::Ordering::Relaxed;
+use ::std::thread;
+
+
+thread_local!
+{
+	static CLUSTER: Cell<usize> = const { Cell::new(0) };
+}
+
+fn cluster_of_current_thread() -> usize
+{
+	CLUSTER.with(|cluster| cluster.get())
+}
+
+fn dsm_synch_queue<T>(configuration: CcQueueConfiguration<T>) -> CcQueue<T, HeapAllocator, DsmSynchStrategy>
+{
+	CcQueue::with_synch_strategy(HeapAllocator, configuration, DsmSynchStrategy)
+}
+
+fn producers_and_consumers(queue: Arc<CcQueue<usize, HeapAllocator, DsmSynchStrategy>>, producers: usize, consumers: usize, items_per_producer: usize)
+{
+	let total = producers * items_per_producer;
+	let dequeued = Arc::new(AtomicUsize::new(0));
+	let sum = Arc::new(AtomicUsize::new(0));
+	
+	let producers: Vec<_> = (0 .. producers).map(|producer|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			CLUSTER.with(|cluster| cluster.set(producer));
+			
+			let mut handle = queue.new_per_thread_handle();
+			for index in 0 .. items_per_producer
+			{
+				handle.enqueue(producer * items_per_producer + index).unwrap()
+			}
+		})
+	}).collect();
+	
+	let consumers: Vec<_> = (0 .. consumers).map(|consumer|
+	{
+		let queue = queue.clone();
+		let dequeued = dequeued.clone();
+		let sum = sum.clone();
+		thread::spawn(move ||
+		{
+			CLUSTER.with(|cluster| cluster.set(consumer));
+			
+			let mut next = vec![0; total / items_per_producer];
+			while dequeued.load(Relaxed) < total
+			{
+				match queue.dequeue()
+				{
+					Ok(item) =>
+					{
+						// Each consumer sees each producer's items in the order they were enqueued.
+						let producer = item / items_per_producer;
+						assert!(item % items_per_producer >= next[producer]);
+						next[producer] = item % items_per_producer + 1;
+						
+						sum.fetch_add(item, Relaxed);
+						dequeued.fetch_add(1, Relaxed);
+					}
+					Err(error) =>
+					{
+						assert_eq!(error, DequeueError::Empty);
+						thread::yield_now()
+					}
+				}
+			}
+		})
+	}).collect();
+	
+	for producer in producers
+	{
+		producer.join().unwrap();
+	}
+	for consumer in consumers
+	{
+		consumer.join().unwrap();
+	}
+	
+	assert_eq!(dequeued.load(Relaxed), total);
+	assert_eq!(sum.load(Relaxed), total * (total - 1) / 2);
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+}
+
+#[test]
+fn single_thread_first_in_first_out()
+{
+	let queue = dsm_synch_queue(CcQueueConfiguration::default());
+	
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+	for item in 0 .. 1_000
+	{
+		queue.enqueue(item.to_string()).unwrap();
+	}
+	for item in 0 .. 1_000
+	{
+		assert_eq!(queue.dequeue(), Ok(item.to_string()));
+	}
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+}
+
+#[test]
+fn many_producers_and_consumers()
+{
+	producers_and_consumers(Arc::new(dsm_synch_queue(CcQueueConfiguration::default())), 4, 4, 20_000);
+}
+
+#[test]
+fn many_producers_and_consumers_in_many_clusters()
+{
+	let queue = dsm_synch_queue(CcQueueConfiguration { clustering: Clustering::Explicit { clusters: 3, cluster_of_current_thread }, ..CcQueueConfiguration::default() });
+	assert_eq!(queue.clusters(), 3);
+	
+	producers_and_consumers(Arc::new(queue), 6, 3, 10_000);
+}
+
+#[test]
+fn handles_can_be_dropped_whilst_other_threads_combine()
+{
+	const THREADS: usize = 8;
+	const ROUNDS: usize = 2_000;
+	
+	let queue = Arc::new(dsm_synch_queue(CcQueueConfiguration::default()));
+	
+	let threads: Vec<_> = (0 .. THREADS).map(|thread|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			for round in 0 .. ROUNDS
+			{
+				let mut handle = queue.new_per_thread_handle();
+				handle.enqueue(thread * ROUNDS + round).unwrap();
+				handle.dequeue().unwrap();
+			}
+		})
+	}).collect();
+	
+	for thread in threads
+	{
+		thread.join().unwrap();
+	}
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+}
+
+#[test]
+fn bounded_batches_and_close()
+{
+	let queue = Arc::new(dsm_synch_queue(CcQueueConfiguration { capacity: Some(4), ..CcQueueConfiguration::default() }));
+	
+	let producer =
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			queue.enqueue_batch(0 .. 100).unwrap();
+			queue.close()
+		})
+	};
+	
+	let mut dequeued = Vec::new();
+	loop
+	{
+		match queue.dequeue_batch(3, &mut dequeued)
+		{
+			Ok(count) => assert!((1 ..= 3).contains(&count)),
+			Err(DequeueError::Empty) => thread::yield_now(),
+			Err(DequeueError::Closed) => break,
+			Err(DequeueError::TimedOut) => unreachable!("dequeue_batch() does not wait"),
+		}
+	}
+	
+	producer.join().unwrap();
+	assert_eq!(dequeued, (0 .. 100).collect::<Vec<_>>());
+	assert_eq!(queue.enqueue(100), Err(100));
+}
@@ -0,0 +1,170 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+#![cfg(unix)]
+
+
+extern crate cc_queue;
+
+
+mod common;
+
+
+use common::kill_children;
+use common::ready;
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::DequeueError;
+use ::cc_queue::allocators::HeapAllocator;
+use ::cc_queue::allocators::MmapFileAllocator;
+use ::std::env;
+use ::std::fs::remove_file;
+use ::std::path::Path;
+use ::std::process;
+use ::std::thread;
+use ::std::time::Duration;
+
+
+const LENGTH: usize = 16 * 1024 * 1024;
+
+const PRODUCERS: u64 = 2;
+
+const KILLS: usize = 50;
+
+
+fn durable() -> CcQueueConfiguration<u64>
+{
+	CcQueueConfiguration
+	{
+		durable: true,
+		..CcQueueConfiguration::default()
+	}
+}
+
+// Items are a producer in the upper 32 bits and that producer's sequence number in the lower.
+fn item(producer: u64, sequence: u64) -> u64
+{
+	producer << 32 | sequence
+}
+
+// Enqueues and dequeues until killed.
+fn child(path: &Path) -> !
+{
+	let allocator = MmapFileAllocator::open(path).unwrap();
+	let queue = unsafe { CcQueue::<u64, MmapFileAllocator>::from_raw(allocator.clone(), allocator.root().unwrap(), durable()).unwrap() };
+	
+	ready();
+	
+	thread::scope(|scope|
+	{
+		for producer in 0 .. PRODUCERS
+		{
+			let queue = &queue;
+			scope.spawn(move ||
+			{
+				for sequence in 0 ..
+				{
+					queue.enqueue(item(producer, sequence)).unwrap()
+				}
+			});
+		}
+		
+		// Slower than the producers, so that the queue is seldom empty.
+		let mut batch = Vec::new();
+		loop
+		{
+			thread::sleep(Duration::from_millis(1));
+			let _ = queue.dequeue();
+			batch.clear();
+			let _ = queue.dequeue_batch(3, &mut batch);
+		}
+	})
+}
+
+#[test]
+fn durable_queue_behaves_as_a_queue()
+{
+	let queue = CcQueue::configured(HeapAllocator, CcQueueConfiguration { capacity: Some(10), ..durable() });
+	
+	queue.enqueue_batch(0 .. 5).unwrap();
+	queue.enqueue(5).unwrap();
+	let mut batch = Vec::new();
+	assert_eq!(queue.dequeue_batch(4, &mut batch), Ok(4));
+	assert_eq!(batch, vec![0, 1, 2, 3]);
+	assert_eq!(queue.dequeue(), Ok(4));
+	
+	queue.close();
+	assert_eq!(queue.dequeue(), Ok(5));
+	assert_eq!(queue.dequeue(), Err(DequeueError::Closed));
+}
+
+#[test]
+fn recovers_a_queue_that_was_not_closed()
+{
+	let path = env::temp_dir().join(format!("cc-queue-{}-recovers_a_queue_that_was_not_closed", process::id()));
+	let _ = remove_file(&path);
+	
+	{
+		let allocator = MmapFileAllocator::create(&path, LENGTH).unwrap();
+		let queue = CcQueue::configured(allocator.clone(), durable());
+		allocator.set_root(Some(queue.as_raw()));
+		queue.enqueue_batch(0 .. 10).unwrap();
+		assert_eq!(queue.dequeue(), Ok(0));
+		
+		// As if the process exited.
+		queue.into_raw();
+	}
+	
+	let allocator = MmapFileAllocator::recover(&path).unwrap();
+	let queue = unsafe { CcQueue::<u64, MmapFileAllocator>::recover(allocator.clone(), allocator.root().unwrap(), durable()).unwrap() };
+	for item in 1 .. 10
+	{
+		assert_eq!(queue.dequeue(), Ok(item));
+	}
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+	
+	drop(queue);
+	let _ = remove_file(&path);
+}
+
+#[test]
+fn survives_being_killed()
+{
+	// Bounded, so that the file does not fill up.
+	let create = |path: &Path|
+	{
+		let allocator = MmapFileAllocator::create(path, LENGTH).unwrap();
+		let queue = CcQueue::configured(allocator.clone(), CcQueueConfiguration { capacity: Some(1000), ..durable() });
+		allocator.set_root(Some(queue.into_raw()));
+	};
+	
+	kill_children("survives_being_killed", KILLS, create, child, |path|
+	{
+		let allocator = MmapFileAllocator::recover(path).unwrap();
+		let queue = unsafe { CcQueue::<u64, MmapFileAllocator>::recover(allocator.clone(), allocator.root().unwrap(), durable()).unwrap() };
+		
+		// Each producer's items that remain are consecutive.
+		let mut previous = [None; PRODUCERS as usize];
+		let mut length = 0;
+		while let Ok(item) = queue.dequeue()
+		{
+			let producer = (item >> 32) as usize;
+			let sequence = item & 0xFFFF_FFFF;
+			if let Some(previous) = previous[producer]
+			{
+				assert_eq!(sequence, previous + 1, "producer {}'s items are not consecutive", producer);
+			}
+			previous[producer] = Some(sequence);
+			length += 1;
+		}
+		
+		// The recovered queue is usable.
+		queue.enqueue_batch(0 .. length).unwrap();
+		for item in 0 .. length
+		{
+			assert_eq!(queue.dequeue(), Ok(item));
+		}
+		assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+	});
+}
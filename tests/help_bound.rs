@@ -0,0 +1,144 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::DequeueError;
+use ::cc_queue::DsmSynchStrategy;
+use ::cc_queue::HelpBound;
+use ::cc_queue::SynchStrategy;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::sync::Arc;
+use ::std::thread;
+use ::std::time::Duration;
+
+
+fn with_help_bound(help_bound: HelpBound) -> CcQueue<usize, HeapAllocator>
+{
+	CcQueue::configured(HeapAllocator, CcQueueConfiguration { help_bound, ..CcQueueConfiguration::default() })
+}
+
+fn adaptive(minimum: usize, maximum: usize, target_hold_time: Duration) -> HelpBound
+{
+	HelpBound::Adaptive { minimum, maximum, target_hold_time }
+}
+
+const THREADS: usize = 4;
+
+const ITEMS_PER_THREAD: usize = 5_000;
+
+// With a help bound of one, every item is handed to a spinning waiter one pass at a time, which is slow on few CPUs.
+const ITEMS_PER_THREAD_WITHOUT_HELP: usize = 250;
+
+// Each of `THREADS` threads enqueues `items_per_thread` distinct items, dequeuing one after each, and the items dequeued are checked to be those enqueued.
+fn producers_and_consumers<S: 'static + SynchStrategy>(queue: CcQueue<usize, HeapAllocator, S>, items_per_thread: usize) -> Arc<CcQueue<usize, HeapAllocator, S>>
+{
+	let queue = Arc::new(queue);
+	
+	let threads: Vec<_> = (0 .. THREADS).map(|thread|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			let mut sum = 0;
+			for index in 0 .. items_per_thread
+			{
+				queue.enqueue(thread * items_per_thread + index).unwrap();
+				sum += queue.dequeue().unwrap();
+			}
+			sum
+		})
+	}).collect();
+	
+	let sum: usize = threads.into_iter().map(|thread| thread.join().unwrap()).sum();
+	let total = THREADS * items_per_thread;
+	assert_eq!(sum, total * (total - 1) / 2);
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+	queue
+}
+
+#[test]
+fn default_help_bound_is_fixed()
+{
+	let queue = CcQueue::<usize, _>::new(HeapAllocator);
+	assert_eq!(queue.enqueue_help_bound(), HelpBound::DEFAULT);
+	assert_eq!(queue.dequeue_help_bound(), HelpBound::DEFAULT);
+	
+	for item in 0 .. 100
+	{
+		queue.enqueue(item).unwrap();
+		queue.dequeue().unwrap();
+	}
+	assert_eq!(queue.enqueue_help_bound(), HelpBound::DEFAULT);
+	assert_eq!(queue.dequeue_help_bound(), HelpBound::DEFAULT);
+}
+
+#[test]
+fn help_bound_of_one_applies_every_request()
+{
+	let queue = producers_and_consumers(with_help_bound(HelpBound::Fixed(1)), ITEMS_PER_THREAD_WITHOUT_HELP);
+	assert_eq!(queue.enqueue_help_bound(), 1);
+	
+	let queue = CcQueue::with_synch_strategy(HeapAllocator, CcQueueConfiguration { help_bound: HelpBound::Fixed(1), ..CcQueueConfiguration::default() }, DsmSynchStrategy);
+	producers_and_consumers(queue, ITEMS_PER_THREAD_WITHOUT_HELP);
+}
+
+#[test]
+#[should_panic]
+fn help_bound_of_zero_is_not_permitted()
+{
+	with_help_bound(HelpBound::Fixed(0));
+}
+
+#[test]
+#[should_panic]
+fn adaptive_minimum_of_zero_is_not_permitted()
+{
+	with_help_bound(adaptive(0, 10, Duration::from_millis(1)));
+}
+
+#[test]
+#[should_panic]
+fn adaptive_minimum_above_maximum_is_not_permitted()
+{
+	with_help_bound(adaptive(10, 9, Duration::from_millis(1)));
+}
+
+#[test]
+fn adaptive_help_bound_starts_at_default_within_limits()
+{
+	assert_eq!(with_help_bound(adaptive(1, 1_000, Duration::from_millis(1))).enqueue_help_bound(), HelpBound::DEFAULT);
+	assert_eq!(with_help_bound(adaptive(1, 16, Duration::from_millis(1))).enqueue_help_bound(), 16);
+	assert_eq!(with_help_bound(adaptive(300, 400, Duration::from_millis(1))).dequeue_help_bound(), 300);
+}
+
+#[test]
+fn adaptive_help_bound_shrinks_to_minimum_when_combining_takes_too_long()
+{
+	let queue = with_help_bound(adaptive(3, 1_000, Duration::from_secs(0)));
+	
+	for item in 0 .. 20
+	{
+		queue.enqueue(item).unwrap();
+	}
+	assert_eq!(queue.enqueue_help_bound(), 3);
+	assert_eq!(queue.dequeue_help_bound(), HelpBound::DEFAULT);
+	
+	for item in 0 .. 20
+	{
+		assert_eq!(queue.dequeue(), Ok(item));
+	}
+	assert_eq!(queue.dequeue_help_bound(), 3);
+}
+
+#[test]
+fn adaptive_help_bound_stays_within_limits()
+{
+	let queue = producers_and_consumers(with_help_bound(adaptive(2, 8, Duration::from_secs(60))), ITEMS_PER_THREAD);
+	assert!((2 ..= 8).contains(&queue.enqueue_help_bound()));
+	assert!((2 ..= 8).contains(&queue.dequeue_help_bound()));
+}
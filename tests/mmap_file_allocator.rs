@@ -0,0 +1,271 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+#![cfg(unix)]
+
+
+extern crate cc_queue;
+
+
+mod common;
+
+
+use common::TemporaryPath;
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::CcSynchStrategy;
+use ::cc_queue::DequeueError;
+use ::cc_queue::DsmSynchStrategy;
+use ::cc_queue::SynchStrategy;
+use ::cc_queue::allocators::Allocator;
+use ::cc_queue::allocators::MmapFileAllocator;
+use ::std::convert::TryInto;
+use ::std::env;
+use ::std::fs;
+use ::std::io::ErrorKind;
+use ::std::path::PathBuf;
+use ::std::process::Command;
+use ::std::ptr::NonNull;
+
+
+const LENGTH: usize = 1024 * 1024;
+
+// Set for the child process of `queue_survives_process_restart`.
+const CHILD_PATH: &str = "CC_QUEUE_MMAP_FILE_ALLOCATOR_CHILD_PATH";
+
+
+fn create_queue<S: SynchStrategy + 'static>(path: &TemporaryPath, strategy: S, items: u64)
+{
+	let allocator = MmapFileAllocator::create(&path.0, LENGTH).unwrap();
+	let queue = CcQueue::with_synch_strategy(allocator.clone(), CcQueueConfiguration::default(), strategy);
+	for item in 0 .. items
+	{
+		queue.enqueue(item).unwrap();
+	}
+	allocator.set_root(Some(queue.into_raw()));
+}
+
+fn reopen_queue<S: SynchStrategy + 'static>(path: &PathBuf) -> (MmapFileAllocator, CcQueue<u64, MmapFileAllocator, S>)
+{
+	let allocator = MmapFileAllocator::open(path).unwrap();
+	let root = allocator.root().expect("root was set");
+	let queue = unsafe { CcQueue::from_raw(allocator.clone(), root, CcQueueConfiguration::default()).unwrap() };
+	(allocator, queue)
+}
+
+// Replaces the only word in the first page of a file, which holds its allocator's header, equal to `value`, as if the file were stale or corrupted.
+fn replace_header_word(path: &TemporaryPath, value: u64, replacement: u64)
+{
+	const WORD: usize = 8;
+	
+	let mut bytes = fs::read(&path.0).unwrap();
+	let word = |bytes: &[u8], index: usize| u64::from_ne_bytes(bytes[index * WORD .. (index + 1) * WORD].try_into().unwrap());
+	let mut indices = (0 .. MmapFileAllocator::PAGE_SIZE / WORD).filter(|&index| word(&bytes, index) == value);
+	let index = indices.next().expect("no word of the header has the value");
+	assert_eq!(indices.next(), None, "more than one word of the header has the value");
+	
+	bytes[index * WORD .. (index + 1) * WORD].copy_from_slice(&replacement.to_ne_bytes());
+	fs::write(&path.0, bytes).unwrap();
+}
+
+fn assert_refused(path: &TemporaryPath)
+{
+	assert_eq!(MmapFileAllocator::open(&path.0).unwrap_err().kind(), ErrorKind::InvalidData);
+	assert_eq!(MmapFileAllocator::open_read_only(&path.0).unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+fn queue_survives_reopening<S: SynchStrategy + 'static>(name: &str, strategy: S)
+{
+	let path = TemporaryPath::new(name);
+	create_queue(&path, strategy, 100);
+	
+	let (allocator, queue) = reopen_queue::<S>(&path.0);
+	for item in 0 .. 50
+	{
+		assert_eq!(queue.dequeue(), Ok(item));
+	}
+	allocator.set_root(Some(queue.into_raw()));
+	drop(allocator);
+	
+	let (allocator, queue) = reopen_queue::<S>(&path.0);
+	for item in 50 .. 100
+	{
+		assert_eq!(queue.dequeue(), Ok(item));
+	}
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+	
+	allocator.set_root::<()>(None);
+	drop(queue);
+	assert_eq!(allocator.allocated(), 0);
+}
+
+#[test]
+fn frees_what_is_allocated()
+{
+	let path = TemporaryPath::new("frees_what_is_allocated");
+	let mut allocator = MmapFileAllocator::create(&path.0, LENGTH).unwrap();
+	assert_eq!(allocator.length(), LENGTH);
+	
+	let page = allocator.align_malloc_page_size::<[u8; 100]>();
+	let cache_line = allocator.align_malloc_cache_line_size::<u64>();
+	let array = allocator.align_malloc_cache_line_size_array::<u64>(1000);
+	assert_eq!(page.as_ptr() as usize % MmapFileAllocator::PAGE_SIZE, 0);
+	assert_eq!(cache_line.as_ptr() as usize % MmapFileAllocator::CACHE_LINE_SIZE, 0);
+	assert_eq!(array.as_ptr() as usize % MmapFileAllocator::CACHE_LINE_SIZE, 0);
+	assert_ne!(allocator.allocated(), 0);
+	
+	allocator.free_page_size(page);
+	allocator.free_cache_line_size(cache_line);
+	allocator.free_cache_line_size_array(array, 1000);
+	assert_eq!(allocator.allocated(), 0);
+}
+
+#[test]
+fn reuses_freed_memory()
+{
+	let path = TemporaryPath::new("reuses_freed_memory");
+	let mut allocator = MmapFileAllocator::create(&path.0, LENGTH).unwrap();
+	
+	for _ in 0 .. LENGTH
+	{
+		let pointer = allocator.align_malloc_cache_line_size::<[u8; 200]>();
+		allocator.free_cache_line_size(pointer);
+	}
+	assert_eq!(allocator.allocated(), 0);
+}
+
+#[test]
+fn root_persists()
+{
+	let path = TemporaryPath::new("root_persists");
+	let mut allocator = MmapFileAllocator::create(&path.0, LENGTH).unwrap();
+	assert_eq!(allocator.root::<u64>(), None);
+	
+	let mut root: NonNull<u64> = allocator.align_malloc_cache_line_size();
+	unsafe { *root.as_mut() = 42 };
+	allocator.set_root(Some(root));
+	drop(allocator);
+	
+	let allocator = MmapFileAllocator::open(&path.0).unwrap();
+	let root = allocator.root::<u64>().expect("root was set");
+	assert_eq!(unsafe { *root.as_ref() }, 42);
+}
+
+#[cfg(not(feature = "offset-pointers"))]
+#[test]
+fn can_not_open_whilst_mapped_by_this_process()
+{
+	let path = TemporaryPath::new("can_not_open_whilst_mapped_by_this_process");
+	let _allocator = MmapFileAllocator::create(&path.0, LENGTH).unwrap();
+	
+	assert_eq!(MmapFileAllocator::open(&path.0).unwrap_err().kind(), ErrorKind::AddrInUse);
+}
+
+#[cfg(feature = "offset-pointers")]
+#[test]
+fn queue_survives_being_mapped_at_another_address()
+{
+	let path = TemporaryPath::new("queue_survives_being_mapped_at_another_address");
+	create_queue(&path, CcSynchStrategy, 100);
+	
+	// Keeps the file mapped at the address it was created at, if it still is.
+	let first = MmapFileAllocator::open(&path.0).unwrap();
+	
+	let (allocator, queue) = reopen_queue::<CcSynchStrategy>(&path.0);
+	assert_ne!(allocator.root::<()>(), first.root::<()>());
+	for item in 0 .. 100
+	{
+		assert_eq!(queue.dequeue(), Ok(item));
+	}
+	queue.enqueue(100).unwrap();
+	allocator.set_root(Some(queue.into_raw()));
+	drop(allocator);
+	
+	let queue = unsafe { CcQueue::<u64, MmapFileAllocator>::from_raw(first.clone(), first.root().unwrap(), CcQueueConfiguration::default()).unwrap() };
+	assert_eq!(queue.dequeue(), Ok(100));
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+	first.set_root::<()>(None);
+	drop(queue);
+	assert_eq!(first.allocated(), 0);
+}
+
+#[test]
+fn queue_survives_reopening_cc_synch()
+{
+	queue_survives_reopening("queue_survives_reopening_cc_synch", CcSynchStrategy)
+}
+
+#[test]
+fn queue_survives_reopening_dsm_synch()
+{
+	queue_survives_reopening("queue_survives_reopening_dsm_synch", DsmSynchStrategy)
+}
+
+#[test]
+fn queue_survives_process_restart()
+{
+	if let Some(path) = env::var_os(CHILD_PATH)
+	{
+		let (allocator, queue) = reopen_queue::<CcSynchStrategy>(&PathBuf::from(path));
+		for item in 0 .. 10
+		{
+			assert_eq!(queue.dequeue(), Ok(item));
+		}
+		queue.enqueue(10).unwrap();
+		allocator.set_root(Some(queue.into_raw()));
+		return
+	}
+	
+	let path = TemporaryPath::new("queue_survives_process_restart");
+	create_queue(&path, CcSynchStrategy, 10);
+	
+	let output = Command::new(env::current_exe().unwrap()).args(["--exact", "queue_survives_process_restart", "--test-threads", "1"]).env(CHILD_PATH, &path.0).output().unwrap();
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+	
+	let (allocator, queue) = reopen_queue::<CcSynchStrategy>(&path.0);
+	assert_eq!(queue.dequeue(), Ok(10));
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+	allocator.set_root::<()>(None);
+	drop(queue);
+	assert_eq!(allocator.allocated(), 0);
+}
+
+#[test]
+fn refuses_a_foreign_file()
+{
+	let path = TemporaryPath::new("refuses_a_foreign_file");
+	fs::write(&path.0, vec![0xAB; LENGTH]).unwrap();
+	assert_refused(&path);
+}
+
+#[test]
+fn refuses_a_file_whose_end_is_beyond_it()
+{
+	let path = TemporaryPath::new("refuses_a_file_whose_end_is_beyond_it");
+	drop(MmapFileAllocator::create(&path.0, LENGTH).unwrap());
+	
+	// Nothing has been allocated, so the end is where the first block would be.
+	replace_header_word(&path, MmapFileAllocator::PAGE_SIZE as u64, 2 * LENGTH as u64);
+	assert_refused(&path);
+}
+
+#[test]
+fn refuses_a_file_whose_free_list_is_out_of_bounds_or_misaligned()
+{
+	let path = TemporaryPath::new("refuses_a_file_whose_free_list_is_out_of_bounds_or_misaligned");
+	{
+		let mut allocator = MmapFileAllocator::create(&path.0, LENGTH).unwrap();
+		let first = allocator.align_malloc_cache_line_size::<u64>();
+		allocator.align_malloc_cache_line_size::<u64>();
+		allocator.free_cache_line_size(first);
+	}
+	
+	// The first block allocated is at the start of the second page, and is now the first block of a free list.
+	let first = MmapFileAllocator::PAGE_SIZE as u64;
+	replace_header_word(&path, first, first + 8);
+	assert_refused(&path);
+	
+	replace_header_word(&path, first + 8, 2 * LENGTH as u64);
+	assert_refused(&path);
+}
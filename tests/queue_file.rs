@@ -0,0 +1,333 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+#![cfg(unix)]
+
+
+extern crate cc_queue;
+
+
+mod common;
+
+
+use common::TemporaryPath;
+use ::cc_queue::BrokenLink;
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::DequeueError;
+use ::cc_queue::QueueFile;
+use ::cc_queue::QueueHeaderError;
+use ::cc_queue::allocators::Allocator;
+use ::cc_queue::allocators::MmapFileAllocator;
+use ::std::env;
+use ::std::io::ErrorKind;
+use ::std::mem::size_of;
+use ::std::process::Command;
+use ::std::process::Output;
+use ::std::ptr::NonNull;
+
+
+const LENGTH: usize = 1024 * 1024;
+
+
+fn create_queue(path: &TemporaryPath, items: u32)
+{
+	let allocator = MmapFileAllocator::create(&path.0, LENGTH).unwrap();
+	let queue = CcQueue::<u32, MmapFileAllocator>::new(allocator.clone());
+	queue.enqueue_batch(0 .. items).unwrap();
+	allocator.set_root(Some(queue.into_raw()));
+}
+
+// A queue in a file, mapped so that it can be broken, with the offsets of its head and tail recorded in its header.
+struct MappedQueue
+{
+	allocator: MmapFileAllocator,
+	head_offset: usize,
+	tail_offset: usize,
+}
+
+impl MappedQueue
+{
+	fn open(path: &TemporaryPath) -> Self
+	{
+		// Read before mapping, as a file can only be mapped once by a process.
+		let (head_offset, tail_offset) =
+		{
+			let file = QueueFile::open(&path.0).unwrap();
+			(file.head_offset(), file.tail_offset())
+		};
+		
+		MappedQueue
+		{
+			allocator: MmapFileAllocator::open(&path.0).unwrap(),
+			head_offset,
+			tail_offset,
+		}
+	}
+	
+	fn queue(&self) -> *mut u8
+	{
+		self.allocator.root::<u8>().unwrap().as_ptr()
+	}
+	
+	fn link_field(&self, offset: usize) -> *mut usize
+	{
+		unsafe { self.queue().add(offset) as *mut usize }
+	}
+	
+	fn head(&self) -> usize
+	{
+		get_link(self.link_field(self.head_offset))
+	}
+	
+	// As if the queue's tail were not written back before a crash.
+	fn make_tail_stale(&self)
+	{
+		set_link(self.link_field(self.tail_offset), self.head())
+	}
+	
+	// A node's link is its first field.
+	fn link_last_node_to(&self, target: usize)
+	{
+		let last = get_link(self.link_field(self.tail_offset));
+		set_link(last as *mut usize, target)
+	}
+	
+	// The word of the queue's header recording the offset of its head, which is followed by that of its tail.
+	fn head_offset_word(&self) -> usize
+	{
+		let header = self.queue() as *mut u64;
+		let words = self.head_offset / size_of::<u64>();
+		let mut fields = (0 .. words - 1).filter(|&word| unsafe { *header.add(word) == self.head_offset as u64 && *header.add(word + 1) == self.tail_offset as u64 });
+		let word = fields.next().expect("no header field records the offset of the head");
+		assert_eq!(fields.next(), None, "more than one header field records the offset of the head");
+		word
+	}
+}
+
+fn get_link(field: *mut usize) -> usize
+{
+	let value = unsafe { *field };
+	if cfg!(feature = "offset-pointers") && value != 0
+	{
+		(field as usize).wrapping_add(value)
+	}
+	else
+	{
+		value
+	}
+}
+
+fn set_link(field: *mut usize, target: usize)
+{
+	let value = if cfg!(feature = "offset-pointers")
+	{
+		target.wrapping_sub(field as usize)
+	}
+	else
+	{
+		target
+	};
+	unsafe { *field = value }
+}
+
+fn item(item: u32) -> Vec<u8>
+{
+	item.to_ne_bytes().to_vec()
+}
+
+fn cc_queue_inspect(arguments: &[&str], path: &TemporaryPath) -> Output
+{
+	Command::new(env!("CARGO_BIN_EXE_cc-queue-inspect")).args(arguments).arg(&path.0).output().unwrap()
+}
+
+#[test]
+fn inspects_a_queue()
+{
+	let path = TemporaryPath::new("inspects_a_queue");
+	{
+		let allocator = MmapFileAllocator::create(&path.0, LENGTH).unwrap();
+		let queue = CcQueue::<u32, MmapFileAllocator>::new(allocator.clone());
+		queue.enqueue_batch(1 .. 4).unwrap();
+		assert_eq!(queue.dequeue(), Ok(1));
+		allocator.set_root(Some(queue.into_raw()));
+	}
+	
+	let file = QueueFile::open(&path.0).unwrap();
+	assert_eq!(file.version(), 1);
+	assert_eq!(file.data_size(), 4);
+	assert_eq!(file.data_alignment(), 4);
+	
+	let inspection = file.inspect();
+	assert_eq!(inspection.items, vec![&item(2)[..], &item(3)[..]]);
+	assert_eq!(inspection.broken_link, None);
+	assert!(inspection.tail_is_last);
+	assert_eq!(inspection.recorded_length, 2);
+	assert!(inspection.is_consistent());
+}
+
+#[test]
+fn repairs_a_stale_tail()
+{
+	let path = TemporaryPath::new("repairs_a_stale_tail");
+	create_queue(&path, 5);
+	MappedQueue::open(&path).make_tail_stale();
+	
+	let mut file = QueueFile::open(&path.0).unwrap();
+	let inspection = file.inspect();
+	assert_eq!(inspection.items.len(), 5);
+	assert_eq!(inspection.broken_link, None);
+	assert!(!inspection.tail_is_last);
+	assert!(!inspection.is_consistent());
+	assert_eq!(file.repair_tail().unwrap_err().kind(), ErrorKind::PermissionDenied);
+	drop(file);
+	
+	let mut file = QueueFile::open_for_repair(&path.0).unwrap();
+	assert!(file.repair_tail().unwrap());
+	assert!(file.inspect().is_consistent());
+	assert!(!file.repair_tail().unwrap());
+	drop(file);
+	
+	let allocator = MmapFileAllocator::open(&path.0).unwrap();
+	let queue = unsafe { CcQueue::<u32, MmapFileAllocator>::from_raw(allocator.clone(), allocator.root().unwrap(), CcQueueConfiguration::default()).unwrap() };
+	queue.enqueue(5).unwrap();
+	for item in 0 .. 6
+	{
+		assert_eq!(queue.dequeue(), Ok(item));
+	}
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+}
+
+#[test]
+fn finds_a_cycle()
+{
+	let path = TemporaryPath::new("finds_a_cycle");
+	create_queue(&path, 3);
+	{
+		let queue = MappedQueue::open(&path);
+		queue.link_last_node_to(queue.head());
+	}
+	
+	let file = QueueFile::open(&path.0).unwrap();
+	let inspection = file.inspect();
+	assert_eq!(inspection.items.len(), 3);
+	assert_eq!(inspection.broken_link, Some(BrokenLink::Cycle(3)));
+	assert!(!inspection.tail_is_last);
+	drop(file);
+	
+	let mut file = QueueFile::open_for_repair(&path.0).unwrap();
+	assert_eq!(file.repair_tail().unwrap_err().kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn finds_a_dangling_link()
+{
+	let path = TemporaryPath::new("finds_a_dangling_link");
+	create_queue(&path, 3);
+	{
+		// Not aligned as a block is.
+		let queue = MappedQueue::open(&path);
+		queue.link_last_node_to(queue.queue() as usize + 8);
+	}
+	
+	let file = QueueFile::open(&path.0).unwrap();
+	let inspection = file.inspect();
+	assert_eq!(inspection.items, vec![&item(0)[..], &item(1)[..], &item(2)[..]]);
+	assert_eq!(inspection.broken_link, Some(BrokenLink::Dangling(3)));
+	assert!(!inspection.is_consistent());
+}
+
+#[test]
+fn refuses_a_file_without_a_queue()
+{
+	let path = TemporaryPath::new("refuses_a_file_without_a_queue");
+	
+	drop(MmapFileAllocator::create(&path.0, LENGTH).unwrap());
+	assert_eq!(QueueFile::open(&path.0).unwrap_err().kind(), ErrorKind::InvalidData);
+	
+	let mut allocator = MmapFileAllocator::open(&path.0).unwrap();
+	let block: NonNull<[u64; 32]> = allocator.align_malloc_page_size();
+	unsafe { block.as_ptr().write([0; 32]) };
+	allocator.set_root(Some(block));
+	drop(allocator);
+	
+	let error = QueueFile::open(&path.0).unwrap_err();
+	assert_eq!(error.kind(), ErrorKind::InvalidData);
+	assert_eq!(error.get_ref().unwrap().downcast_ref::<QueueHeaderError>(), Some(&QueueHeaderError::NotAQueue));
+}
+
+#[test]
+fn refuses_a_queue_whose_header_records_a_corrupted_head_offset()
+{
+	let path = TemporaryPath::new("refuses_a_queue_whose_header_records_a_corrupted_head_offset");
+	create_queue(&path, 3);
+	let (word, head_offset) =
+	{
+		let queue = MappedQueue::open(&path);
+		(queue.head_offset_word(), queue.head_offset as u64)
+	};
+	
+	// Beyond the file, so large that finding the end of the head overflows, and not aligned.
+	for &corrupted in [LENGTH as u64, u64::MAX - 7, head_offset + 1].iter()
+	{
+		{
+			let allocator = MmapFileAllocator::open(&path.0).unwrap();
+			unsafe { *allocator.root::<u64>().unwrap().as_ptr().add(word) = corrupted };
+		}
+		assert_eq!(QueueFile::open(&path.0).unwrap_err().kind(), ErrorKind::InvalidData, "head offset {} was not refused", corrupted);
+	}
+}
+
+#[test]
+#[should_panic(expected = "memory-mapped file is read-only")]
+fn a_read_only_file_can_not_be_allocated_from()
+{
+	let path = TemporaryPath::new("a_read_only_file_can_not_be_allocated_from");
+	drop(MmapFileAllocator::create(&path.0, LENGTH).unwrap());
+	
+	let mut allocator = MmapFileAllocator::open_read_only(&path.0).unwrap();
+	allocator.align_malloc_cache_line_size::<u64>();
+}
+
+#[test]
+fn cc_queue_inspect_reports_and_repairs()
+{
+	let path = TemporaryPath::new("cc_queue_inspect_reports_and_repairs");
+	create_queue(&path, 2);
+	MappedQueue::open(&path).make_tail_stale();
+	
+	let output = cc_queue_inspect(&[], &path);
+	assert_eq!(output.status.code(), Some(1));
+	let report = String::from_utf8(output.stdout).unwrap();
+	assert!(report.contains("items: 2\n"), "{}", report);
+	assert!(report.contains("chain: whole\n"), "{}", report);
+	assert!(report.contains("tail: not the last node\n"), "{}", report);
+	
+	let output = cc_queue_inspect(&["--json"], &path);
+	assert_eq!(output.status.code(), Some(1));
+	let report = String::from_utf8(output.stdout).unwrap();
+	assert!(report.contains("\"broken_link\":null,\"tail_is_last\":false"), "{}", report);
+	let items = format!("\"items\":[{:?},{:?}]", item(0), item(1)).replace(' ', "");
+	assert!(report.contains(&items), "{}", report);
+	
+	let output = cc_queue_inspect(&["--repair-tail"], &path);
+	assert_eq!(output.status.code(), Some(0));
+	assert!(String::from_utf8(output.stdout).unwrap().contains("repaired: yes\n"));
+	
+	let output = cc_queue_inspect(&[], &path);
+	assert_eq!(output.status.code(), Some(0));
+	let report = String::from_utf8(output.stdout).unwrap();
+	let hex = |item: u32| item.to_ne_bytes().iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+	assert!(report.contains(&format!("0: {}\n1: {}\n", hex(0), hex(1))), "{}", report);
+}
+
+#[test]
+fn cc_queue_inspect_fails_for_a_missing_file()
+{
+	let path = TemporaryPath::new("cc_queue_inspect_fails_for_a_missing_file");
+	
+	let output = cc_queue_inspect(&[], &path);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(!output.stderr.is_empty());
+}
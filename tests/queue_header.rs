@@ -0,0 +1,108 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::DsmSynchStrategy;
+use ::cc_queue::QueueHeaderError;
+use ::cc_queue::allocators::HeapAllocator;
+#[cfg(unix)] use ::cc_queue::allocators::MmapFileAllocator;
+#[cfg(unix)] use ::std::env;
+#[cfg(unix)] use ::std::fs::remove_file;
+#[cfg(unix)] use ::std::process;
+use ::std::ptr::NonNull;
+
+
+// The header's format version follows its 8 byte magic number.
+const VERSION_OFFSET: usize = 8;
+
+
+#[test]
+fn reopens_a_queue_of_the_same_type()
+{
+	let queue = CcQueue::<u64, HeapAllocator>::new(HeapAllocator);
+	queue.enqueue(1).unwrap();
+	let raw = queue.into_raw();
+	
+	let queue = unsafe { CcQueue::<u64, HeapAllocator>::from_raw(HeapAllocator, raw, CcQueueConfiguration::default()) }.unwrap();
+	assert_eq!(queue.dequeue(), Ok(1));
+}
+
+#[test]
+fn refuses_to_reopen_a_queue_as_a_different_type()
+{
+	let raw = CcQueue::<u64, HeapAllocator>::new(HeapAllocator).into_raw();
+	
+	let error = unsafe { CcQueue::<u32, HeapAllocator>::from_raw(HeapAllocator, raw, CcQueueConfiguration::default()) }.unwrap_err();
+	assert_eq!(error, QueueHeaderError::LayoutMismatch { field: "size of data", found: 8, expected: 4 });
+	assert_eq!(error.to_string(), "queue's size of data is 8, but 4 was expected");
+	
+	drop(unsafe { CcQueue::<u64, HeapAllocator>::from_raw(HeapAllocator, raw, CcQueueConfiguration::default()) }.unwrap());
+}
+
+#[test]
+fn refuses_to_reopen_a_queue_with_a_different_synch_strategy()
+{
+	let raw = CcQueue::<u64, HeapAllocator>::new(HeapAllocator).into_raw();
+	
+	match unsafe { CcQueue::<u64, HeapAllocator, DsmSynchStrategy>::from_raw(HeapAllocator, raw, CcQueueConfiguration::default()) }
+	{
+		Err(QueueHeaderError::LayoutMismatch { field, .. }) => assert_eq!(field, "layout hash"),
+		other => panic!("reopened with a different synch strategy: {:?}", other.map(|_| ())),
+	}
+	
+	drop(unsafe { CcQueue::<u64, HeapAllocator>::from_raw(HeapAllocator, raw, CcQueueConfiguration::default()) }.unwrap());
+}
+
+#[test]
+fn refuses_to_reopen_a_queue_of_another_version()
+{
+	let raw = CcQueue::<u64, HeapAllocator>::new(HeapAllocator).into_raw();
+	let version = unsafe { raw.cast::<u8>().as_ptr().add(VERSION_OFFSET) as *mut u64 };
+	let original = unsafe { version.read() };
+	
+	unsafe { version.write(original + 1) };
+	let error = unsafe { CcQueue::<u64, HeapAllocator>::from_raw(HeapAllocator, raw, CcQueueConfiguration::default()) }.unwrap_err();
+	assert_eq!(error, QueueHeaderError::UnsupportedVersion(original + 1));
+	
+	unsafe { version.write(original) };
+	drop(unsafe { CcQueue::<u64, HeapAllocator>::from_raw(HeapAllocator, raw, CcQueueConfiguration::default()) }.unwrap());
+}
+
+#[test]
+fn refuses_to_open_memory_that_is_not_a_queue()
+{
+	let mut memory = vec![0u64; 64];
+	let raw = NonNull::new(memory.as_mut_ptr()).unwrap().cast();
+	
+	let error = unsafe { CcQueue::<u64, HeapAllocator>::from_raw(HeapAllocator, raw, CcQueueConfiguration::default()) }.unwrap_err();
+	assert_eq!(error, QueueHeaderError::NotAQueue);
+	
+	let error = unsafe { CcQueue::<u64, HeapAllocator>::attach(HeapAllocator, raw, CcQueueConfiguration::default()) }.unwrap_err();
+	assert_eq!(error, QueueHeaderError::NotAQueue);
+}
+
+#[cfg(unix)]
+#[test]
+fn refuses_to_reopen_a_queue_that_was_dropped()
+{
+	let path = env::temp_dir().join(format!("cc-queue-{}-refuses_to_reopen_a_queue_that_was_dropped", process::id()));
+	let _ = remove_file(&path);
+	
+	{
+		let allocator = MmapFileAllocator::create(&path, 1024 * 1024).unwrap();
+		let queue = CcQueue::<u64, MmapFileAllocator>::new(allocator.clone());
+		allocator.set_root(Some(queue.as_raw()));
+	}
+	
+	let allocator = MmapFileAllocator::open(&path).unwrap();
+	let error = unsafe { CcQueue::<u64, MmapFileAllocator>::from_raw(allocator.clone(), allocator.root().unwrap(), CcQueueConfiguration::default()) }.unwrap_err();
+	assert_eq!(error, QueueHeaderError::NotAQueue);
+	
+	drop(allocator);
+	let _ = remove_file(&path);
+}
@@ -0,0 +1,204 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+#![cfg(unix)]
+
+
+extern crate cc_queue;
+
+
+mod common;
+
+
+use common::kill_children;
+use common::ready;
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::DequeueError;
+use ::cc_queue::EnqueueError;
+use ::cc_queue::RecordedOperation;
+use ::cc_queue::allocators::HeapAllocator;
+use ::cc_queue::allocators::MmapFileAllocator;
+use ::std::env;
+use ::std::fs::remove_file;
+use ::std::path::Path;
+use ::std::process;
+use ::std::thread;
+use ::std::time::Duration;
+
+
+const LENGTH: usize = 16 * 1024 * 1024;
+
+const PRODUCER: usize = 0;
+
+const CONSUMER: usize = 1;
+
+const KILLS: usize = 20;
+
+
+fn recoverable() -> CcQueueConfiguration<u64>
+{
+	CcQueueConfiguration
+	{
+		durable: true,
+		operation_records: 2,
+		..CcQueueConfiguration::default()
+	}
+}
+
+// Enqueues consecutive items, and dequeues them, until killed.
+fn child(path: &Path) -> !
+{
+	let allocator = MmapFileAllocator::open(path).unwrap();
+	let queue = unsafe { CcQueue::<u64, MmapFileAllocator>::from_raw(allocator.clone(), allocator.root().unwrap(), recoverable()).unwrap() };
+	
+	ready();
+	
+	thread::scope(|scope|
+	{
+		let queue = &queue;
+		scope.spawn(move ||
+		{
+			let mut producer = queue.new_recoverable_per_thread_handle(PRODUCER);
+			for item in 0 ..
+			{
+				producer.enqueue(item).unwrap()
+			}
+		});
+		
+		// Slower than the producer, so that the queue is seldom empty.
+		let mut consumer = queue.new_recoverable_per_thread_handle(CONSUMER);
+		loop
+		{
+			thread::sleep(Duration::from_millis(1));
+			let _ = consumer.dequeue();
+		}
+	})
+}
+
+#[test]
+fn records_the_last_single_operation()
+{
+	let mut queue = CcQueue::configured(HeapAllocator, CcQueueConfiguration { capacity: Some(1), operation_records: 1, ..CcQueueConfiguration::default() });
+	assert_eq!(queue.operation_records(), 1);
+	assert_eq!(queue.recorded_operation(0), RecordedOperation::None);
+	
+	queue.new_recoverable_per_thread_handle(0).enqueue(1).unwrap();
+	assert_eq!(queue.recorded_operation(0), RecordedOperation::Enqueued);
+	
+	assert_eq!(queue.new_recoverable_per_thread_handle(0).try_enqueue(2), Err(EnqueueError::Full(2)));
+	assert_eq!(queue.recorded_operation(0), RecordedOperation::NotEnqueued(2));
+	
+	assert_eq!(queue.new_recoverable_per_thread_handle(0).dequeue(), Ok(1));
+	assert_eq!(queue.recorded_operation(0), RecordedOperation::Dequeued(1));
+	
+	assert_eq!(queue.new_recoverable_per_thread_handle(0).dequeue(), Err(DequeueError::Empty));
+	assert_eq!(queue.recorded_operation(0), RecordedOperation::NotDequeued);
+	
+	// Batches are not recorded.
+	queue.new_recoverable_per_thread_handle(0).enqueue_batch(Some(3)).unwrap();
+	assert_eq!(queue.recorded_operation(0), RecordedOperation::NotDequeued);
+	
+	queue.close();
+	assert_eq!(queue.new_recoverable_per_thread_handle(0).enqueue(4), Err(4));
+	assert_eq!(queue.recorded_operation(0), RecordedOperation::NotEnqueued(4));
+}
+
+#[test]
+#[should_panic(expected = "operation record is already in use by a per-thread handle")]
+fn an_operation_record_is_used_by_one_handle_at_a_time()
+{
+	let queue = CcQueue::<u64, HeapAllocator>::configured(HeapAllocator, CcQueueConfiguration { operation_records: 1, ..CcQueueConfiguration::default() });
+	let _handle = queue.new_recoverable_per_thread_handle(0);
+	queue.new_recoverable_per_thread_handle(0);
+}
+
+#[test]
+#[should_panic(expected = "record must be less than the number of operation records")]
+fn an_operation_record_must_exist()
+{
+	let queue = CcQueue::<u64, HeapAllocator>::configured(HeapAllocator, CcQueueConfiguration { operation_records: 1, ..CcQueueConfiguration::default() });
+	queue.new_recoverable_per_thread_handle(1);
+}
+
+#[test]
+fn operation_records_survive_reopening()
+{
+	let path = env::temp_dir().join(format!("cc-queue-{}-operation_records_survive_reopening", process::id()));
+	let _ = remove_file(&path);
+	
+	{
+		let allocator = MmapFileAllocator::create(&path, LENGTH).unwrap();
+		let queue = CcQueue::configured(allocator.clone(), recoverable());
+		queue.new_recoverable_per_thread_handle(PRODUCER).enqueue_batch(0 .. 2).unwrap();
+		assert_eq!(queue.new_recoverable_per_thread_handle(CONSUMER).dequeue(), Ok(0));
+		allocator.set_root(Some(queue.as_raw()));
+		queue.into_raw();
+	}
+	
+	let allocator = MmapFileAllocator::recover(&path).unwrap();
+	let mut queue = unsafe { CcQueue::<u64, MmapFileAllocator>::recover(allocator.clone(), allocator.root().unwrap(), recoverable()).unwrap() };
+	assert_eq!(queue.operation_records(), 2);
+	assert_eq!(queue.recorded_operation(PRODUCER), RecordedOperation::None);
+	assert_eq!(queue.recorded_operation(CONSUMER), RecordedOperation::Dequeued(0));
+	queue.new_recoverable_per_thread_handle(PRODUCER).enqueue(2).unwrap();
+	assert_eq!(queue.recorded_operation(PRODUCER), RecordedOperation::Enqueued);
+	
+	drop(queue);
+	let _ = remove_file(&path);
+}
+
+#[test]
+fn reports_whether_operations_in_progress_took_effect()
+{
+	// Bounded, so that the file does not fill up.
+	let create = |path: &Path|
+	{
+		let allocator = MmapFileAllocator::create(path, LENGTH).unwrap();
+		let queue = CcQueue::configured(allocator.clone(), CcQueueConfiguration { capacity: Some(1000), ..recoverable() });
+		allocator.set_root(Some(queue.into_raw()));
+	};
+	
+	kill_children("reports_whether_operations_in_progress_took_effect", KILLS, create, child, |path|
+	{
+		let allocator = MmapFileAllocator::recover(path).unwrap();
+		let mut queue = unsafe { CcQueue::<u64, MmapFileAllocator>::recover(allocator.clone(), allocator.root().unwrap(), recoverable()).unwrap() };
+		
+		let mut remaining = Vec::new();
+		while let Ok(item) = queue.dequeue()
+		{
+			remaining.push(item)
+		}
+		
+		// Exactly once: the consumer's last dequeue, if it took effect, is of the item before those remaining, and the producer's last enqueue, if it did not, is of the item after them.
+		let mut last = remaining.last().cloned();
+		match queue.recorded_operation(CONSUMER)
+		{
+			RecordedOperation::Dequeued(item) =>
+			{
+				match remaining.first()
+				{
+					Some(&first) => assert_eq!(item + 1, first, "the dequeue that took effect was not of the item before those remaining"),
+					None => last = Some(item),
+				}
+			}
+			RecordedOperation::NotDequeued | RecordedOperation::None => (),
+			recorded => panic!("consumer recorded {:?}", recorded),
+		}
+		match queue.recorded_operation(PRODUCER)
+		{
+			RecordedOperation::NotEnqueued(item) => if let Some(last) = last
+			{
+				assert_eq!(item, last + 1, "the enqueue that did not take effect was not of the item after those remaining")
+			},
+			RecordedOperation::Enqueued | RecordedOperation::None => (),
+			recorded => panic!("producer recorded {:?}", recorded),
+		}
+		
+		for (index, &item) in remaining.iter().enumerate()
+		{
+			assert_eq!(item, remaining[0] + index as u64, "items remaining are not consecutive")
+		}
+	});
+}
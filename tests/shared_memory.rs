@@ -0,0 +1,217 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+#![cfg(unix)]
+
+
+extern crate cc_queue;
+extern crate libc;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::CcQueueConfiguration;
+use ::cc_queue::allocators::HeapAllocator;
+use ::cc_queue::allocators::MmapFileAllocator;
+use ::libc::_exit;
+use ::libc::c_int;
+use ::libc::fork;
+use ::libc::waitpid;
+use ::libc::WEXITSTATUS;
+use ::libc::WIFEXITED;
+use ::std::env;
+use ::std::panic;
+use ::std::panic::AssertUnwindSafe;
+use ::std::panic::catch_unwind;
+use ::std::process;
+use ::std::process::Command;
+use ::std::ptr::NonNull;
+use ::std::time::Duration;
+
+
+const LENGTH: usize = 16 * 1024 * 1024;
+
+const PRODUCERS: u64 = 3;
+
+const ITEMS: u64 = 1000;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// Set for the child process of `unrelated_processes_share_a_queue_by_name`.
+const CHILD_NAME: &str = "CC_QUEUE_SHARED_MEMORY_CHILD_NAME";
+
+// Set for the child process of `unrelated_processes_share_a_queue_by_file_descriptor`.
+const CHILD_FD: &str = "CC_QUEUE_SHARED_MEMORY_CHILD_FD";
+
+
+fn shared_between_processes(capacity: Option<usize>) -> CcQueueConfiguration<u64>
+{
+	CcQueueConfiguration
+	{
+		capacity,
+		shared_between_processes: true,
+		..CcQueueConfiguration::default()
+	}
+}
+
+// Items are a producer in the upper 32 bits and that producer's sequence number in the lower.
+fn item(producer: u64, sequence: u64) -> u64
+{
+	producer << 32 | sequence
+}
+
+// Dequeues the items of `producers`, checking that each producer's are in order.
+fn consume(queue: &CcQueue<u64, MmapFileAllocator>, producers: u64)
+{
+	let mut next = vec![0; producers as usize];
+	for _ in 0 .. producers * ITEMS
+	{
+		let item = queue.dequeue_timeout(TIMEOUT).expect("items from other processes were not dequeued in time");
+		let producer = (item >> 32) as usize;
+		assert_eq!(item & 0xFFFF_FFFF, next[producer], "producer {}'s items are out of order", producer);
+		next[producer] += 1;
+	}
+}
+
+fn wait_for_child(child: c_int)
+{
+	let mut status = 0;
+	assert_eq!(unsafe { waitpid(child, &mut status, 0) }, child);
+	assert!(WIFEXITED(status) && WEXITSTATUS(status) == 0, "child failed");
+}
+
+// Runs in a forked child; exits rather than returning, so that nothing the parent owns is dropped.
+fn forked_producer(inherited: &CcQueue<u64, MmapFileAllocator>, allocator: MmapFileAllocator, raw: NonNull<()>, producer: u64) -> !
+{
+	let succeeded = catch_unwind(AssertUnwindSafe(||
+	{
+		// The copy of the queue belongs to the parent.
+		panic::set_hook(Box::new(|_| ()));
+		assert!(catch_unwind(AssertUnwindSafe(|| inherited.enqueue(0))).is_err(), "a forked child could use its copy of the queue");
+		drop(panic::take_hook());
+		
+		let queue = unsafe { CcQueue::<u64, MmapFileAllocator>::attach(allocator, raw, CcQueueConfiguration::default()).unwrap() };
+		for sequence in 0 .. ITEMS
+		{
+			queue.enqueue(item(producer, sequence)).unwrap()
+		}
+	})).is_ok();
+	
+	unsafe { _exit(if succeeded { 0 } else { 1 }) }
+}
+
+// Enqueues items from another process.
+fn unrelated_child(allocator: MmapFileAllocator) -> !
+{
+	let queue = unsafe { CcQueue::<u64, MmapFileAllocator>::attach(allocator.clone(), allocator.root().unwrap(), CcQueueConfiguration::default()).unwrap() };
+	for sequence in 0 .. ITEMS
+	{
+		queue.enqueue(item(0, sequence)).unwrap()
+	}
+	
+	drop(queue);
+	process::exit(0)
+}
+
+// Shares a queue with a process started from this test binary.
+fn share_with_unrelated_process(allocator: MmapFileAllocator, test: &str, variable: &str, value: String)
+{
+	// Bounded, so that the producer waits for the consumer in another process, as well as the other way round.
+	let queue = CcQueue::configured(allocator.clone(), shared_between_processes(Some(16)));
+	allocator.set_root(Some(queue.as_raw()));
+	
+	let mut child = Command::new(env::current_exe().unwrap()).args(["--exact", test, "--nocapture", "--test-threads", "1"]).env(variable, value).spawn().unwrap();
+	consume(&queue, 1);
+	assert!(child.wait().unwrap().success(), "child failed");
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn forked_processes_share_a_queue()
+{
+	let allocator = MmapFileAllocator::create_anonymous_shared_memory(LENGTH).unwrap();
+	
+	// Bounded, so that producers wait for the consumer in another process.
+	let queue = CcQueue::configured(allocator.clone(), shared_between_processes(Some(16)));
+	
+	// So that the children have copies of this thread's handle.
+	queue.enqueue(0).unwrap();
+	assert_eq!(queue.dequeue(), Ok(0));
+	
+	let raw = queue.as_raw();
+	let children: Vec<c_int> = (0 .. PRODUCERS).map(|producer| match unsafe { fork() }
+	{
+		-1 => panic!("fork() failed"),
+		0 => forked_producer(&queue, allocator.clone(), raw, producer),
+		child => child,
+	}).collect();
+	
+	consume(&queue, PRODUCERS);
+	for child in children
+	{
+		wait_for_child(child)
+	}
+	
+	// The children's handles, and the nodes they pooled, were freed.
+	drop(queue);
+	assert_eq!(allocator.allocated(), 0);
+}
+
+#[test]
+fn unrelated_processes_share_a_queue_by_name()
+{
+	if let Ok(name) = env::var(CHILD_NAME)
+	{
+		unrelated_child(MmapFileAllocator::open_shared_memory(&name).unwrap())
+	}
+	
+	let name = format!("/cc-queue-{}-unrelated_processes_share_a_queue_by_name", process::id());
+	let allocator = MmapFileAllocator::create_shared_memory(&name, LENGTH).unwrap();
+	share_with_unrelated_process(allocator, "unrelated_processes_share_a_queue_by_name", CHILD_NAME, name.clone());
+	MmapFileAllocator::unlink_shared_memory(&name).unwrap();
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn unrelated_processes_share_a_queue_by_file_descriptor()
+{
+	use ::libc::fcntl;
+	use ::libc::F_SETFD;
+	use ::std::fs::File;
+	use ::std::os::unix::io::AsRawFd;
+	use ::std::os::unix::io::FromRawFd;
+	
+	if let Ok(fd) = env::var(CHILD_FD)
+	{
+		unrelated_child(MmapFileAllocator::from_file(unsafe { File::from_raw_fd(fd.parse().unwrap()) }).unwrap())
+	}
+	
+	let allocator = MmapFileAllocator::create_anonymous_shared_memory(LENGTH).unwrap();
+	
+	// So that the child inherits the file descriptor.
+	let fd = allocator.as_raw_fd();
+	assert_eq!(unsafe { fcntl(fd, F_SETFD, 0) }, 0);
+	share_with_unrelated_process(allocator, "unrelated_processes_share_a_queue_by_file_descriptor", CHILD_FD, fd.to_string());
+}
+
+#[test]
+#[should_panic(expected = "a queue can not be both durable and shared between processes")]
+fn a_queue_shared_between_processes_can_not_be_durable()
+{
+	CcQueue::configured(HeapAllocator, CcQueueConfiguration { durable: true, ..shared_between_processes(None) });
+}
+
+#[test]
+#[should_panic(expected = "a queue shared between processes can not have operation records")]
+fn a_queue_shared_between_processes_can_not_have_operation_records()
+{
+	CcQueue::configured(HeapAllocator, CcQueueConfiguration { operation_records: 1, ..shared_between_processes(None) });
+}
+
+#[test]
+#[should_panic(expected = "queue must be shared between processes to be attached")]
+fn only_a_queue_shared_between_processes_can_be_attached()
+{
+	let queue = CcQueue::<u64, HeapAllocator>::new(HeapAllocator);
+	unsafe { CcQueue::<u64, HeapAllocator>::attach(HeapAllocator, queue.as_raw(), CcQueueConfiguration::default()).unwrap() };
+}
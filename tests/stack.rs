@@ -0,0 +1,167 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcStack;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::cell::Cell;
+use ::std::mem::forget;
+use ::std::sync::Arc;
+use ::std::thread;
+
+
+thread_local!
+{
+	static DROPPED: Cell<usize> = const { Cell::new(0) };
+}
+
+fn dropped() -> usize
+{
+	DROPPED.with(|dropped| dropped.get())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Counted(usize);
+
+impl Drop for Counted
+{
+	fn drop(&mut self)
+	{
+		DROPPED.with(|dropped| dropped.set(dropped.get() + 1))
+	}
+}
+
+#[test]
+fn pop_returns_items_in_reverse_order_of_push()
+{
+	let stack = CcStack::new(HeapAllocator);
+	let mut handle = stack.new_per_thread_handle();
+	
+	assert_eq!(handle.pop(), None);
+	handle.push(1);
+	handle.push(2);
+	handle.push(3);
+	assert_eq!(handle.pop(), Some(3));
+	handle.push(4);
+	assert_eq!(handle.pop(), Some(4));
+	assert_eq!(handle.pop(), Some(2));
+	assert_eq!(handle.pop(), Some(1));
+	assert_eq!(handle.pop(), None);
+}
+
+#[test]
+fn clear_passes_remaining_items_most_recent_first()
+{
+	let mut stack = CcStack::new(HeapAllocator);
+	{
+		let mut handle = stack.new_per_thread_handle();
+		for item in 0 .. 5
+		{
+			handle.push(item.to_string());
+		}
+		assert_eq!(handle.pop(), Some("4".to_string()));
+	}
+	
+	let mut cleared = Vec::new();
+	stack.clear(|item| cleared.push(item));
+	stack.clear(|item| cleared.push(item));
+	assert_eq!(cleared, vec!["3", "2", "1", "0"]);
+	
+	let mut handle = stack.new_per_thread_handle();
+	assert_eq!(handle.pop(), None);
+	handle.push("5".to_string());
+	assert_eq!(handle.pop(), Some("5".to_string()));
+}
+
+#[test]
+fn clear_large_stack_does_not_overflow_the_call_stack()
+{
+	const ITEMS: usize = 1_000_000;
+	
+	let mut stack = CcStack::new(HeapAllocator);
+	{
+		let mut handle = stack.new_per_thread_handle();
+		for item in 0 .. ITEMS
+		{
+			handle.push(item);
+		}
+	}
+	
+	let mut count = 0;
+	stack.clear(|_| count += 1);
+	assert_eq!(count, ITEMS);
+}
+
+#[test]
+fn drop_stack_runs_destructor_on_each_remaining_item_exactly_once()
+{
+	let before = dropped();
+	{
+		let stack = CcStack::new(HeapAllocator);
+		let mut handle = stack.new_per_thread_handle();
+		for index in 0 .. 10
+		{
+			handle.push(Counted(index));
+		}
+		assert_eq!(handle.pop().map(|item| item.0), Some(9));
+		assert_eq!(dropped() - before, 1);
+	}
+	assert_eq!(dropped() - before, 10);
+}
+
+#[test]
+fn drop_stack_with_destructor()
+{
+	let before = dropped();
+	{
+		let stack = CcStack::with_destructor(HeapAllocator, forget);
+		let mut handle = stack.new_per_thread_handle();
+		for index in 0 .. 10
+		{
+			handle.push(Counted(index));
+		}
+	}
+	assert_eq!(dropped(), before);
+}
+
+#[test]
+fn many_threads_push_and_pop()
+{
+	const THREADS: usize = 8;
+	const ITEMS_PER_THREAD: usize = 10_000;
+	
+	let stack = Arc::new(CcStack::new(HeapAllocator));
+	
+	let threads: Vec<_> = (0 .. THREADS).map(|thread|
+	{
+		let stack = stack.clone();
+		thread::spawn(move ||
+		{
+			let mut handle = stack.new_per_thread_handle();
+			let mut popped = Vec::new();
+			for index in 0 .. ITEMS_PER_THREAD
+			{
+				handle.push(thread * ITEMS_PER_THREAD + index);
+				if index % 2 == 1
+				{
+					popped.extend(handle.pop());
+				}
+			}
+			popped
+		})
+	}).collect();
+	
+	let mut popped: Vec<usize> = threads.into_iter().flat_map(|thread| thread.join().unwrap()).collect();
+	
+	let mut handle = stack.new_per_thread_handle();
+	while let Some(item) = handle.pop()
+	{
+		popped.push(item)
+	}
+	
+	popped.sort();
+	assert_eq!(popped, (0 .. THREADS * ITEMS_PER_THREAD).collect::<Vec<_>>());
+}
@@ -0,0 +1,123 @@
+// This file is part of cc-queue. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT. No part of cc-queue, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of cc-queue. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/cc-queue/master/COPYRIGHT.
+
+
+extern crate cc_queue;
+
+
+use ::cc_queue::CcQueue;
+use ::cc_queue::DequeueError;
+use ::cc_queue::allocators::HeapAllocator;
+use ::std::sync::Arc;
+use ::std::thread;
+
+
+const PRODUCERS: usize = 4;
+
+const CONSUMERS: usize = 4;
+
+const ITEMS_PER_PRODUCER: usize = 10_000;
+
+#[test]
+fn enqueue_and_dequeue_without_explicit_handles()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	
+	queue.enqueue(1).unwrap();
+	queue.enqueue(2).unwrap();
+	assert_eq!(queue.dequeue(), Ok(1));
+	assert_eq!(queue.dequeue(), Ok(2));
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+}
+
+#[test]
+fn implicit_and_explicit_handles_share_a_queue()
+{
+	let queue = CcQueue::new(HeapAllocator);
+	
+	queue.enqueue(1).unwrap();
+	{
+		let mut handle = queue.new_per_thread_handle();
+		handle.enqueue(2).unwrap();
+		assert_eq!(handle.dequeue(), Ok(1));
+	}
+	assert_eq!(queue.dequeue(), Ok(2));
+}
+
+#[test]
+fn many_producers_and_consumers_on_threads_that_exit()
+{
+	let queue = Arc::new(CcQueue::new(HeapAllocator));
+	
+	let producers: Vec<_> = (0 .. PRODUCERS).map(|producer|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			for index in 0 .. ITEMS_PER_PRODUCER
+			{
+				queue.enqueue(producer * ITEMS_PER_PRODUCER + index).unwrap()
+			}
+		})
+	}).collect();
+	
+	let consumers: Vec<_> = (0 .. CONSUMERS).map(|_|
+	{
+		let queue = queue.clone();
+		thread::spawn(move ||
+		{
+			let mut dequeued = Vec::new();
+			while dequeued.len() < PRODUCERS * ITEMS_PER_PRODUCER / CONSUMERS
+			{
+				if let Ok(item) = queue.dequeue()
+				{
+					dequeued.push(item)
+				}
+			}
+			dequeued
+		})
+	}).collect();
+	
+	for producer in producers
+	{
+		producer.join().unwrap();
+	}
+	
+	let mut dequeued: Vec<usize> = consumers.into_iter().flat_map(|consumer| consumer.join().unwrap()).collect();
+	dequeued.sort();
+	assert_eq!(dequeued, (0 .. PRODUCERS * ITEMS_PER_PRODUCER).collect::<Vec<_>>());
+	
+	assert_eq!(queue.dequeue(), Err(DequeueError::Empty));
+}
+
+#[test]
+fn queue_dropped_before_threads_that_used_it_exit()
+{
+	let (sender, receiver) = ::std::sync::mpsc::channel();
+	let (dropped_sender, dropped_receiver) = ::std::sync::mpsc::channel::<()>();
+	
+	let thread = thread::spawn(move ||
+	{
+		let queue: Arc<CcQueue<usize, HeapAllocator>> = receiver.recv().unwrap();
+		queue.enqueue(1).unwrap();
+		drop(queue);
+		dropped_receiver.recv().unwrap();
+		
+		// A new queue may reuse the freed memory of the old queue.
+		let queue = CcQueue::new(HeapAllocator);
+		queue.enqueue(2).unwrap();
+		assert_eq!(queue.dequeue(), Ok(2));
+	});
+	
+	let queue = Arc::new(CcQueue::new(HeapAllocator));
+	sender.send(queue.clone()).unwrap();
+	while Arc::strong_count(&queue) != 1
+	{
+		thread::yield_now()
+	}
+	assert_eq!(queue.dequeue(), Ok(1));
+	drop(queue);
+	dropped_sender.send(()).unwrap();
+	
+	thread.join().unwrap();
+}